@@ -0,0 +1,36 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Writes `bundled_words.rs`, a table of every word list in `words/` as
+/// `(file name, contents)`, for `words.rs` to include. Without the directory
+/// the table is empty and lists are downloaded as needed.
+fn main() {
+    println!("cargo:rerun-if-changed=words");
+
+    let mut entries = Vec::new();
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("words");
+    let listing = env::var_os("CARGO_FEATURE_BUNDLED_WORDS").and_then(|_| fs::read_dir(&dir).ok());
+    if let Some(listing) = listing {
+        for entry in listing.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                println!("cargo:rerun-if-changed={}", path.display());
+                entries.push((entry.file_name().to_string_lossy().to_string(), path));
+            }
+        }
+    }
+    entries.sort();
+
+    let mut table = String::from("&[\n");
+    for (name, path) in entries {
+        table.push_str(&format!(
+            "    ({name:?}, include_str!({:?})),\n",
+            path.to_string_lossy()
+        ));
+    }
+    table.push(']');
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("bundled_words.rs");
+    fs::write(out, table).unwrap();
+}
@@ -1,47 +1,304 @@
-use rand::seq::SliceRandom;
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::popup::{PopupAction, PopupManager};
-use crate::words::{download, generate_words};
+use crate::code::CodeSource;
+use crate::config::Config;
+use crate::ghost::Ghost;
+use crate::history::{self, Record};
+use crate::keylog::{Key, KeyEvent};
+use crate::keys::{KeyStats, Weaknesses};
+use crate::passages::Passages;
+use crate::popup::{DownloadStatus, PopupAction, PopupManager};
+use crate::punctuation::{self, WordOptions};
+use crate::quotes::{Quote, QuoteLength, QuotePool};
+use crate::replay;
+use crate::themes::{self, Theme};
+use crate::ui::CursorStyle;
+use crate::words::{
+    cache_dir, downloaded, generate_weighted_words, generate_words, is_right_to_left,
+    starts_right_to_left, Download,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum Mode {
+    Words {
+        count: usize,
+    },
+    Time {
+        seconds: usize,
+    },
+    Quote {
+        length: Option<QuoteLength>,
+    },
+    /// Text from a file or stdin
+    Custom,
+    Code,
+    /// Words chosen to practice weak keys
+    Practice {
+        count: usize,
+    },
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mode::Words { count } => write!(f, "words {count}"),
+            Mode::Time { seconds } => write!(f, "time {seconds}s"),
+            Mode::Quote {
+                length: Some(length),
+            } => write!(f, "quote {length}"),
+            Mode::Quote { length: None } => write!(f, "quote"),
+            Mode::Custom => write!(f, "custom"),
+            Mode::Code => write!(f, "code"),
+            Mode::Practice { count } => write!(f, "practice {count}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharCounts {
+    pub correct: usize,
+    pub incorrect: usize,
+    pub extra: usize,
+    pub missed: usize,
+}
+
+/// Speed and errors during one second of a test. The last one covers
+/// whatever is left when the test ends, so it can be shorter or longer.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    /// Seconds from the start of the test to the end of this snapshot
+    pub second: f64,
+    /// Net speed from the start of the test up to this second
+    pub wpm: f64,
+    /// Raw speed within this second alone
+    pub raw_wpm: f64,
+    /// Keystrokes within this second
+    pub keystrokes: usize,
+    /// Wrong keystrokes within this second
+    pub errors: usize,
+}
+
+/// Summary of a finished test, shown on the results screen.
+#[derive(Debug, Clone)]
+pub struct Results {
+    pub net_wpm: f64,
+    pub raw_wpm: f64,
+    /// Keystroke accuracy, counting corrected mistakes
+    pub accuracy: f64,
+    /// Accuracy of the text as submitted
+    pub final_accuracy: f64,
+    pub consistency: f64,
+    pub chars: CharCounts,
+    pub duration: f64,
+    pub mode: Mode,
+    pub word_list: String,
+    pub snapshots: Vec<Snapshot>,
+}
+
+/// Splits text into the user-perceived characters that are typed one at a time.
+fn graphemes(text: &str) -> Vec<String> {
+    text.graphemes(true).map(|g| g.to_string()).collect()
+}
 
 #[derive(Debug)]
 pub struct App {
-    target: String,
-    input: String,
+    target: Vec<String>,
+    /// One entry per target grapheme typed so far, `None` where it was skipped
+    input: Vec<Option<String>>,
     start_time: Option<Instant>,
     end_time: Option<Instant>,
     time_limit: Option<Duration>,
+    word_count: usize,
+    word_list: String,
+    /// The word list's `rightToLeft` flag, kept to avoid reading the list on
+    /// every restart
+    word_list_right_to_left: bool,
+    word_options: WordOptions,
+    /// Bias generated words toward weak keys
+    practice: bool,
+    ghost: Option<Ghost>,
+    quotes: Option<QuotePool>,
+    quote: Option<Quote>,
+    passages: Option<Passages>,
+    code: Option<CodeSource>,
+    /// Whether the target is laid out right-to-left
+    right_to_left: bool,
+    keystrokes: usize,
+    errors: usize,
+    /// Every keystroke of this test, in order
+    events: Vec<KeyEvent>,
+    /// Per-key results of this test, added to the stored totals when it ends
+    key_stats: KeyStats,
+    snapshots: Vec<Snapshot>,
+    /// Keystrokes and errors counted up to the last snapshot
+    snapshot_totals: (usize, usize),
+    recorded: bool,
+    config: Config,
+    themes: Vec<Theme>,
     pub scheme_index: usize,
     pub cursor_style_index: usize,
     pub popup_manager: PopupManager,
+    /// Word list being downloaded, applied once it's done
+    download: Option<Download>,
 }
 
 impl App {
-    pub fn new(word_count: usize, time_limit_seconds: Option<usize>) -> Self {
-        let target = generate_words(word_count, None);
+    pub fn new(word_count: usize, time_limit_seconds: Option<usize>, config: Config) -> Self {
+        let word_list = config.word_list.clone();
+        let word_options = WordOptions {
+            punctuation: config.punctuation,
+            numbers: config.numbers,
+        };
+        let target = punctuation::apply(generate_words(word_count, Some(&word_list)), word_options);
+        let word_list_right_to_left = is_right_to_left(&word_list);
+        let right_to_left = word_list_right_to_left || starts_right_to_left(&target.join(" "));
+        let themes = themes::load();
+        let names: Vec<String> = themes.iter().map(|theme| theme.name.clone()).collect();
+        let scheme_index = names
+            .iter()
+            .position(|name| name == &config.color_scheme)
+            .unwrap_or(0);
+        let cursor_style_index = CursorStyle::index_of(&config.cursor_style).unwrap_or(0);
 
         Self {
-            target: target.join(" "),
-            input: String::new(),
+            target: graphemes(&target.join(" ")),
+            input: Vec::new(),
             start_time: None,
             end_time: None,
             time_limit: time_limit_seconds.map(|s| Duration::from_secs(s as u64)),
-            scheme_index: 0,
-            cursor_style_index: 0,
-            popup_manager: PopupManager::new(),
+            word_count,
+            word_list,
+            word_options,
+            practice: false,
+            ghost: None,
+            quotes: None,
+            quote: None,
+            passages: None,
+            code: None,
+            word_list_right_to_left,
+            right_to_left,
+            keystrokes: 0,
+            errors: 0,
+            events: Vec::new(),
+            key_stats: KeyStats::default(),
+            snapshots: Vec::new(),
+            snapshot_totals: (0, 0),
+            recorded: false,
+            scheme_index,
+            cursor_style_index,
+            config,
+            themes,
+            popup_manager: PopupManager::new(names, scheme_index, cursor_style_index, word_options),
+            download: None,
+        }
+    }
+
+    pub fn with_quotes(quotes: QuotePool, config: Config) -> Self {
+        let quote = quotes.choose();
+
+        Self {
+            target: graphemes(&quote.text),
+            right_to_left: starts_right_to_left(&quote.text),
+            quotes: Some(quotes),
+            quote: Some(quote),
+            ..Self::new(0, None, config)
+        }
+    }
+
+    pub fn with_passages(passages: Passages, config: Config) -> Self {
+        Self {
+            target: graphemes(passages.current()),
+            right_to_left: starts_right_to_left(passages.current()),
+            passages: Some(passages),
+            ..Self::new(0, None, config)
+        }
+    }
+
+    pub fn with_code(code: CodeSource, config: Config) -> Self {
+        Self {
+            target: graphemes(&code.choose()),
+            code: Some(code),
+            right_to_left: false,
+            ..Self::new(0, None, config)
         }
     }
 
+    pub fn with_practice(word_count: usize, config: Config) -> Self {
+        let mut app = Self {
+            practice: true,
+            ..Self::new(word_count, None, config)
+        };
+        app.restart();
+        app
+    }
+
+    /// A one-off test of the given text, like the shared words of a race.
+    pub fn with_text(text: &str, config: Config) -> Self {
+        Self {
+            target: graphemes(text),
+            right_to_left: starts_right_to_left(text),
+            ..Self::new(text.split_whitespace().count(), None, config)
+        }
+    }
+
+    /// An app showing the text of a recorded test, to be driven by `replay`.
+    pub fn replaying(record: &Record, config: Config) -> Self {
+        // Records name the list without the `.json` of its file
+        let word_list = format!("{}.json", record.word_list);
+        let mut app = Self {
+            target: graphemes(&record.target),
+            word_list_right_to_left: is_right_to_left(&word_list),
+            word_list,
+            ..Self::new(0, None, config)
+        };
+        app.reset();
+        app
+    }
+
+    pub fn with_ghost(self, ghost: Option<Ghost>) -> Self {
+        Self { ghost, ..self }
+    }
+
+    /// Overrides the saved punctuation and numbers settings for this session.
+    pub fn with_word_options(mut self, options: WordOptions) -> Self {
+        if options != self.word_options {
+            self.word_options = options;
+            self.popup_manager.set_word_options(options);
+            self.restart();
+        }
+        self
+    }
+
     pub fn restart(&mut self) {
-        let word_count = self.target.split_whitespace().count();
-        self.target = generate_words(word_count, None).join(" ");
-        self.input.clear();
-        self.start_time = None;
-        self.end_time = None;
+        let text = if let Some(code) = &self.code {
+            code.choose()
+        } else if let Some(passages) = &self.passages {
+            passages.current().to_string()
+        } else if let Some(quotes) = &self.quotes {
+            let quote = quotes.choose();
+            let text = quote.text.clone();
+            self.quote = Some(quote);
+            text
+        } else {
+            self.generate_words().join(" ")
+        };
+        self.target = graphemes(&text);
+        self.reset();
     }
 
     pub fn is_done(&self) -> bool {
-        let length_complete = self.input.len() >= self.target.len();
+        // The last grapheme may still be waiting for a combining character
+        let last_in_progress = match (self.input.last(), self.target.last()) {
+            (Some(Some(typed)), Some(expected)) => {
+                typed != expected && expected.starts_with(typed.as_str())
+            }
+            _ => false,
+        };
+        let length_complete = self.input.len() >= self.target.len() && !last_in_progress;
         let time_complete = self
             .time_limit
             .and_then(|limit| self.start_time.map(|start| start.elapsed() >= limit))
@@ -50,86 +307,151 @@ impl App {
         length_complete || time_complete
     }
 
+    /// Called on every iteration of the event loop to take per-second snapshots
+    /// and to close off time-limited tests that ran out without a keypress.
+    /// Also checks on a word list download.
+    pub fn tick(&mut self) {
+        self.poll_download();
+        let Some(start) = self.start_time else {
+            return;
+        };
+
+        if self.end_time.is_none() {
+            if self.is_done() {
+                let limit = self.time_limit.unwrap_or_default();
+                self.end_at(start + limit.min(start.elapsed()));
+            } else {
+                self.take_snapshots_until(start.elapsed());
+            }
+        }
+    }
+
     pub fn handle_char(&mut self, ch: char) {
-        if self.start_time.is_none() {
-            self.start_time = Some(Instant::now());
+        if self.is_done() {
+            return;
         }
 
-        if self.is_done() || self.input.len() >= self.target.len() {
+        // Enter and Tab are only typed in code mode
+        let is_code = self.code.is_some();
+        if matches!(ch, '\n' | '\t') && !is_code {
             return;
         }
 
-        if ch == ' ' {
-            self.handle_space();
-        } else {
-            self.input.push(ch);
+        if self.start_time.is_none() {
+            self.start_time = Some(Instant::now());
+        }
+
+        self.keystrokes += 1;
+        let now = self.elapsed();
+        let latency = self
+            .events
+            .last()
+            .map(|last| now.saturating_sub(last.elapsed()));
+        self.log(match ch {
+            ' ' if !is_code => Key::Skip(' '),
+            '\n' => Key::Skip('\n'),
+            _ => Key::Char(ch),
+        });
+        match ch {
+            ' ' if !is_code => self.handle_space(),
+            '\n' => self.handle_enter(),
+            '\t' => self.handle_tab(),
+            _ => self.type_char(ch, latency),
         }
 
         // Set end time when test is completed
         if self.is_done() && self.end_time.is_none() {
-            self.end_time = Some(Instant::now());
+            self.end_at(Instant::now());
+        }
+    }
+
+    /// Handles the keys that type or erase text.
+    pub fn handle_typing_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        match (code, modifiers) {
+            (KeyCode::Char('h'), KeyModifiers::CONTROL) => self.handle_ctrl_backspace(), // Ctrl+Backspace in Ubuntu
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => self.handle_ctrl_backspace(),
+            (KeyCode::Backspace, KeyModifiers::CONTROL) => self.handle_ctrl_backspace(),
+            (KeyCode::Delete, KeyModifiers::CONTROL) => self.handle_ctrl_backspace(),
+            (KeyCode::Enter, _) => self.handle_char('\n'),
+            (KeyCode::Tab, _) => self.handle_char('\t'),
+            (KeyCode::Backspace, _) => self.handle_backspace(),
+            (KeyCode::Char(ch), KeyModifiers::NONE | KeyModifiers::SHIFT) => self.handle_char(ch),
+            _ => {}
         }
     }
 
+    /// Removes the last typed character. A grapheme built from several
+    /// keystrokes (like a consonant and vowel sign) is taken apart one at a time.
     pub fn handle_backspace(&mut self) {
-        if !self.input.is_empty() {
-            self.input.pop();
+        if self.is_done() {
+            return;
+        }
+        self.log(Key::Backspace);
+        if let Some(Some(last)) = self.input.last_mut() {
+            last.pop();
+            if !last.is_empty() {
+                return;
+            }
         }
+        self.input.pop();
     }
 
     pub fn handle_ctrl_backspace(&mut self) {
+        if self.is_done() {
+            return;
+        }
+        self.log(Key::CtrlBackspace);
         if self.input.is_empty() {
             return;
         }
 
-        let mut chars: Vec<char> = self.input.chars().collect();
+        let is_whitespace =
+            |typed: &Option<String>| typed.as_deref().is_some_and(|g| g.trim().is_empty());
 
-        // Remove trailing spaces
-        while chars.last() == Some(&' ') {
-            chars.pop();
+        // Remove trailing whitespace
+        while self.input.last().is_some_and(is_whitespace) {
+            self.input.pop();
         }
 
-        // Remove characters until we hit a space or beginning (delete whole word)
-        while !chars.is_empty() && chars.last() != Some(&' ') {
-            chars.pop();
+        // Remove graphemes until we hit whitespace or beginning (delete whole word)
+        while self.input.last().is_some_and(|typed| !is_whitespace(typed)) {
+            self.input.pop();
         }
+    }
 
-        self.input = chars.into_iter().collect();
+    /// Every keystroke that typed something, right or wrong, per minute.
+    pub fn raw_wpm(&self) -> f64 {
+        self.per_minute(self.keystrokes)
     }
 
-    pub fn wpm(&self) -> f64 {
-        if let Some(start) = self.start_time {
-            let elapsed = if let Some(end) = self.end_time {
-                // Use the time when test completed, not current time
-                end.duration_since(start).as_secs_f64()
-            } else {
-                // Test still in progress, use current time
-                start.elapsed().as_secs_f64()
-            };
+    /// Only characters of correctly typed words (and the spaces after them)
+    /// per minute.
+    pub fn net_wpm(&self) -> f64 {
+        self.per_minute(self.correct_word_chars())
+    }
 
-            if elapsed > 0.0 {
-                (self.input.len() as f64 / 5.0) / (elapsed / 60.0)
-            } else {
-                0.0
-            }
+    /// Share of keystrokes that matched the expected character, so mistakes
+    /// count even after they're corrected.
+    pub fn accuracy(&self) -> f64 {
+        if self.keystrokes == 0 {
+            100.0
         } else {
-            0.0
+            (self.keystrokes - self.errors) as f64 / self.keystrokes as f64 * 100.0
         }
     }
 
-    pub fn accuracy(&self) -> f64 {
+    /// Share of the text as it stands that is typed correctly.
+    pub fn final_accuracy(&self) -> f64 {
         if self.input.is_empty() {
             100.0
         } else {
             let mut total_count = 0;
             let mut correct_count = 0;
 
-            for (i, input_ch) in self.input.chars().enumerate() {
+            for (typed, expected) in self.input.iter().zip(&self.target) {
                 total_count += 1;
-                if let Some(target_ch) = self.target.chars().nth(i) {
-                    if input_ch == target_ch {
-                        correct_count += 1;
-                    }
+                if typed.as_ref() == Some(expected) {
+                    correct_count += 1;
                 }
             }
 
@@ -141,76 +463,826 @@ impl App {
         }
     }
 
-    pub fn target(&self) -> &str {
+    /// Seconds between the first keystroke and the end of the test.
+    pub fn duration(&self) -> f64 {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => end.duration_since(start).as_secs_f64(),
+            (Some(start), None) => start.elapsed().as_secs_f64(),
+            _ => 0.0,
+        }
+    }
+
+    pub fn char_counts(&self) -> CharCounts {
+        let mut counts = CharCounts::default();
+
+        for (typed, expected) in self.input.iter().zip(&self.target) {
+            match typed {
+                None => counts.missed += 1,
+                Some(g) if g == expected => counts.correct += 1,
+                Some(_) if expected.trim().is_empty() => counts.extra += 1,
+                Some(_) => counts.incorrect += 1,
+            }
+        }
+
+        counts
+    }
+
+    /// Consistency of the per-second raw speed, mapped from its coefficient
+    /// of variation onto 0-100% the same way monkeytype does.
+    pub fn consistency(&self) -> f64 {
+        let per_second: Vec<f64> = self.snapshots.iter().map(|s| s.raw_wpm).collect();
+
+        let mean = per_second.iter().sum::<f64>() / per_second.len() as f64;
+        if per_second.len() < 2 || mean == 0.0 {
+            return 100.0;
+        }
+
+        let variance =
+            per_second.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / per_second.len() as f64;
+        let cov = variance.sqrt() / mean;
+        100.0 * (1.0 - (cov + cov.powi(3) / 3.0 + cov.powi(5) / 5.0).tanh())
+    }
+
+    pub fn mode(&self) -> Mode {
+        if self.code.is_some() {
+            Mode::Code
+        } else if self.passages.is_some() {
+            Mode::Custom
+        } else if let Some(quotes) = &self.quotes {
+            Mode::Quote {
+                length: quotes.length(),
+            }
+        } else if self.practice {
+            Mode::Practice {
+                count: self.word_count,
+            }
+        } else if let Some(limit) = self.time_limit {
+            Mode::Time {
+                seconds: limit.as_secs() as usize,
+            }
+        } else {
+            Mode::Words {
+                count: self.word_count,
+            }
+        }
+    }
+
+    pub fn results(&self) -> Results {
+        let chars = self.char_counts();
+        let word_list = if let Some(code) = &self.code {
+            code.name()
+        } else if let Some(passages) = &self.passages {
+            passages.name().to_string()
+        } else if let Some(quotes) = &self.quotes {
+            quotes.language().to_string()
+        } else {
+            self.word_list.trim_end_matches(".json").to_string()
+        };
+
+        Results {
+            net_wpm: self.net_wpm(),
+            raw_wpm: self.raw_wpm(),
+            accuracy: self.accuracy(),
+            final_accuracy: self.final_accuracy(),
+            consistency: self.consistency(),
+            chars,
+            duration: self.duration(),
+            mode: self.mode(),
+            word_list,
+            snapshots: self.snapshots.clone(),
+        }
+    }
+
+    /// Writes a finished test to history and saves its replay, once per
+    /// test.
+    pub fn save_finished(&mut self) {
+        if let Some(record) = self.finish() {
+            // A failed write shouldn't interrupt the session
+            let _ = history::append(&record);
+            let _ = replay::save(&record, &self.events);
+        }
+    }
+
+    /// Returns the history record of a finished test, once per test.
+    fn finish(&mut self) -> Option<Record> {
+        if self.recorded || self.start_time.is_none() || !self.is_done() {
+            return None;
+        }
+        self.recorded = true;
+
+        let results = self.results();
+        // The next test continues with the following paragraph
+        if let Some(passages) = &mut self.passages {
+            passages.advance();
+        }
+        // Losing key stats only makes practice a little less targeted
+        let _ = self.key_stats.append();
+
+        Some(Record {
+            timestamp: history::now(),
+            mode: results.mode,
+            word_list: results.word_list,
+            wpm: results.net_wpm,
+            raw_wpm: results.raw_wpm,
+            keystroke_accuracy: Some(results.accuracy),
+            final_accuracy: results.final_accuracy,
+            duration: results.duration,
+            target: self.target.concat(),
+        })
+    }
+
+    /// Brings the test to how it was `at` a point of a recorded keystroke log.
+    /// Keys are applied directly, so this works whatever the original mode.
+    /// Moving forward only applies the keys since the last call; seeking back
+    /// starts over from the beginning.
+    pub fn replay(&mut self, events: &[KeyEvent], at: Duration) {
+        if self.events.last().is_some_and(|event| event.elapsed() > at) {
+            self.reset();
+        }
+        if self.end_time.is_some() {
+            return;
+        }
+        let Some(start) = Instant::now().checked_sub(at) else {
+            return;
+        };
+        self.start_time = Some(start);
+
+        let already_applied = self.events.len();
+        let mut applied = already_applied;
+        for event in events[already_applied..]
+            .iter()
+            .take_while(|event| event.elapsed() <= at)
+        {
+            applied += 1;
+            self.take_snapshots_until(event.elapsed());
+            if matches!(event.key, Key::Char(_) | Key::Skip(_)) {
+                self.keystrokes += 1;
+            }
+            match event.key {
+                Key::Char('\t') => self.handle_tab(),
+                Key::Char(ch) => self.type_char(ch, None),
+                Key::Skip('\n') => self.handle_enter(),
+                Key::Skip(_) => self.handle_space(),
+                Key::Backspace => self.handle_backspace(),
+                Key::CtrlBackspace => self.handle_ctrl_backspace(),
+            }
+            if self.is_done() {
+                self.end_at(start + event.elapsed());
+                break;
+            }
+        }
+        // Backspaces logged themselves again with the wrong time
+        self.events.truncate(already_applied);
+        self.events
+            .extend_from_slice(&events[already_applied..applied]);
+        if self.end_time.is_none() {
+            self.take_snapshots_until(at);
+        }
+    }
+
+    /// Where the ghost caret is, once the test is under way.
+    pub fn ghost_position(&self) -> Option<usize> {
+        let start = self.start_time.filter(|_| !self.is_done())?;
+        Some(self.ghost.as_ref()?.position(start.elapsed()))
+    }
+
+    pub fn target(&self) -> &[String] {
         &self.target
     }
 
-    pub fn input(&self) -> &str {
+    pub fn input(&self) -> &[Option<String>] {
         &self.input
     }
 
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.scheme_index % self.themes.len()]
+    }
+
+    pub fn quote(&self) -> Option<&Quote> {
+        self.quote.as_ref()
+    }
+
+    pub fn is_right_to_left(&self) -> bool {
+        self.right_to_left
+    }
+
     pub fn toggle_popup(&mut self) {
         self.popup_manager.toggle();
     }
 
-    pub async fn handle_popup_key(&mut self, key_code: crossterm::event::KeyCode) -> bool {
+    pub fn handle_popup_key(&mut self, key_code: KeyCode) -> bool {
         match self.popup_manager.handle_key(key_code) {
             PopupAction::SelectWordList(selected) => {
-                let word_count = self.target.split_whitespace().count();
-
-                // Try to download the language file first
-                if let Ok(words) = download(&selected).await {
-                    let sampled_words: Vec<String> = words
-                        .choose_multiple(&mut rand::thread_rng(), word_count)
-                        .cloned()
-                        .collect();
-                    self.target = sampled_words.join(" ");
-                    self.input.clear();
-                    self.start_time = None;
-                    self.end_time = None;
-                    self.popup_manager.refresh_languages();
+                if downloaded().contains(&selected) {
+                    self.use_word_list(selected);
                 } else {
-                    // Fall back to generate_words if download fails
-                    self.target = generate_words(word_count, Some(&selected)).join(" ");
-                    self.input.clear();
-                    self.start_time = None;
-                    self.end_time = None;
+                    // Replacing a running download cancels it
+                    let sources = self.config.word_list_sources();
+                    self.download = Some(Download::start(cache_dir(), &selected, sources));
+                    self.poll_download();
                 }
                 true
             }
+            PopupAction::CancelDownload => {
+                self.download = None;
+                self.popup_manager.set_download(None);
+                true
+            }
             PopupAction::SelectColorScheme(index) => {
                 self.scheme_index = index;
+                self.config.color_scheme = self.theme().name.clone();
+                self.save_config();
                 true
             }
             PopupAction::SelectCursorStyle(index) => {
                 self.cursor_style_index = index;
+                self.config.cursor_style = CursorStyle::cycle(index).to_string();
+                self.save_config();
+                true
+            }
+            PopupAction::SetWordOptions(options) => {
+                self.set_word_options(options);
+                self.save_config();
+                // Only word tests are generated; other texts pick it up later
+                if self.quotes.is_none() && self.passages.is_none() && self.code.is_none() {
+                    self.restart();
+                }
                 true
             }
             PopupAction::Close | PopupAction::None => self.popup_manager.is_open(),
         }
     }
 
-    fn handle_space(&mut self) {
-        let pos = self.input.len();
+    /// Applies options picked in the popup. Only the option that changed goes
+    /// into the config, so a command line override of the other isn't saved.
+    fn set_word_options(&mut self, options: WordOptions) {
+        if options.punctuation != self.word_options.punctuation {
+            self.config.punctuation = options.punctuation;
+        }
+        if options.numbers != self.word_options.numbers {
+            self.config.numbers = options.numbers;
+        }
+        self.word_options = options;
+    }
+
+    /// Switches to a word list that's available offline.
+    fn use_word_list(&mut self, word_list: String) {
+        // Picking a word list leaves quote, custom text and code modes
+        self.quotes = None;
+        self.quote = None;
+        self.passages = None;
+        self.code = None;
+        if self.word_count == 0 {
+            self.word_count = self.config.words;
+        }
+        self.word_list_right_to_left = is_right_to_left(&word_list);
+        self.word_list = word_list.clone();
+        self.config.word_list = word_list;
+        self.save_config();
+        self.popup_manager.close();
+        self.restart();
+    }
+
+    /// Shows how the download is going, and applies the list once it's done.
+    fn poll_download(&mut self) {
+        let Some(download) = &mut self.download else {
+            return;
+        };
+        let name = download.filename().to_string();
 
-        if let Some(next_space_offset) = self.target[pos..].find(' ') {
-            // Fill missing characters with '#' and add space
-            for _ in 0..next_space_offset {
-                self.input.push('#');
+        match download.finished() {
+            None => {
+                let progress = download.progress();
+                self.popup_manager
+                    .set_download(Some(DownloadStatus::Running { name, progress }));
+            }
+            Some(Ok(())) => {
+                self.download = None;
+                self.popup_manager.set_download(None);
+                self.popup_manager.refresh_languages();
+                self.use_word_list(name);
+            }
+            Some(Err(_)) => {
+                // The old list stays; the popup marks the failed one
+                self.download = None;
+                self.popup_manager
+                    .set_download(Some(DownloadStatus::Failed { name }));
             }
-            self.input.push(' ');
+        }
+    }
+
+    fn save_config(&self) {
+        // Settings still apply to this session if they can't be written
+        let _ = self.config.save();
+    }
+
+    fn reset(&mut self) {
+        self.right_to_left = self.detect_direction();
+        self.input.clear();
+        self.start_time = None;
+        self.end_time = None;
+        self.keystrokes = 0;
+        self.errors = 0;
+        self.events.clear();
+        self.key_stats = KeyStats::default();
+        self.snapshots.clear();
+        self.snapshot_totals = (0, 0);
+        self.recorded = false;
+    }
+
+    /// Time since the first keystroke, for timestamping the log.
+    fn elapsed(&self) -> Duration {
+        self.start_time
+            .map_or(Duration::ZERO, |start| start.elapsed())
+    }
+
+    /// Records a keystroke at the current cursor position. Nothing is logged
+    /// before the test has started.
+    fn log(&mut self, key: Key) {
+        if self.start_time.is_some() {
+            self.events
+                .push(KeyEvent::new(self.elapsed(), self.input.len(), key));
+        }
+    }
+
+    /// Words for a new test, drawn toward weak keys in practice mode.
+    fn generate_words(&self) -> Vec<String> {
+        let words = if self.practice {
+            let weaknesses = Weaknesses::new(&KeyStats::load());
+            generate_weighted_words(self.word_count, Some(&self.word_list), |word| {
+                weaknesses.weight(word)
+            })
         } else {
-            // Fill remaining characters with '#'
-            let remaining = self.target.len() - pos;
-            for _ in 0..remaining {
-                self.input.push('#');
+            generate_words(self.word_count, Some(&self.word_list))
+        };
+        punctuation::apply(words, self.word_options)
+    }
+
+    /// Word lists say which way they're written; other text is guessed from
+    /// its first letter. Code always runs left-to-right.
+    fn detect_direction(&self) -> bool {
+        if self.code.is_some() {
+            return false;
+        }
+        let words_mode = self.quotes.is_none() && self.passages.is_none();
+        (words_mode && self.word_list_right_to_left) || starts_right_to_left(&self.target.concat())
+    }
+
+    /// Words per minute for `count` characters over the test so far, taking
+    /// five characters as a word.
+    fn per_minute(&self, count: usize) -> f64 {
+        let minutes = self.duration() / 60.0;
+        if minutes > 0.0 {
+            (count as f64 / 5.0) / minutes
+        } else {
+            0.0
+        }
+    }
+
+    /// Characters of words typed without a mistake, counting the separator
+    /// after each. The word being typed counts while it's right so far.
+    fn correct_word_chars(&self) -> usize {
+        let mut total = 0;
+        let mut word = 0;
+        let mut word_correct = true;
+
+        for (typed, expected) in self.input.iter().zip(&self.target) {
+            let correct = typed.as_ref() == Some(expected);
+            if expected.trim().is_empty() {
+                if word_correct && correct {
+                    total += word + 1;
+                }
+                word = 0;
+                word_correct = true;
+            } else {
+                word += 1;
+                word_correct &= correct;
+            }
+        }
+        if word_correct {
+            total += word;
+        }
+
+        total
+    }
+
+    /// Takes a snapshot for every whole second up to `elapsed`.
+    fn take_snapshots_until(&mut self, elapsed: Duration) {
+        let whole_seconds = elapsed.as_secs() as usize;
+        while self.snapshots.len() < whole_seconds {
+            self.take_snapshot((self.snapshots.len() + 1) as f64);
+        }
+    }
+
+    /// Sets the end time and closes off the snapshots with the part of a
+    /// second left over, so the last keystrokes count too.
+    fn end_at(&mut self, end: Instant) {
+        self.end_time = Some(end);
+        let Some(start) = self.start_time else {
+            return;
+        };
+        let elapsed = end.saturating_duration_since(start);
+        self.take_snapshots_until(elapsed);
+
+        let end = elapsed.as_secs_f64();
+        let last = self.snapshots.last().map_or(0.0, |s| s.second);
+        if end <= last {
+            return;
+        }
+        // A sliver of a second would make a wild speed, so it joins the
+        // second before it instead
+        if end - last < 0.5 {
+            if let Some(joined) = self.snapshots.pop() {
+                self.snapshot_totals.0 -= joined.keystrokes;
+                self.snapshot_totals.1 -= joined.errors;
             }
         }
+        self.take_snapshot(end);
+    }
+
+    /// Snapshot of the time since the last one, ending `end` seconds in.
+    fn take_snapshot(&mut self, end: f64) {
+        let start = self.snapshots.last().map_or(0.0, |s| s.second);
+        let (keystrokes, errors) = self.snapshot_totals;
+        let keystrokes = self.keystrokes - keystrokes;
+
+        self.snapshots.push(Snapshot {
+            second: end,
+            wpm: (self.correct_word_chars() as f64 / 5.0) / (end / 60.0),
+            raw_wpm: keystrokes as f64 / (end - start) * 60.0 / 5.0,
+            keystrokes,
+            errors: self.errors - errors,
+        });
+        self.snapshot_totals = (self.keystrokes, self.errors);
+    }
+
+    /// Marks the rest of the current word (or line) as skipped up to the next
+    /// `separator` and types it. Without one, the rest of the target is skipped.
+    /// Skipping anything counts as a wrong keystroke.
+    fn skip_to(&mut self, separator: &str) {
+        let pos = self.input.len();
+        if self.target.get(pos).is_some_and(|g| g != separator) {
+            self.errors += 1;
+        }
+
+        match self.target[pos..].iter().position(|g| g == separator) {
+            Some(offset) => {
+                self.input.extend(std::iter::repeat(None).take(offset));
+                self.input.push(Some(separator.to_string()));
+            }
+            None => self.input.resize(self.target.len(), None),
+        }
+    }
+
+    /// Types a single character. Characters that combine with the previous one
+    /// (vowel signs, accents, joiners) extend that grapheme instead of starting
+    /// a new one.
+    fn type_char(&mut self, ch: char, latency: Option<Duration>) {
+        let pos = self.input.len();
+        if let Some(Some(last)) = self.input.last_mut() {
+            let combined = format!("{last}{ch}");
+            if combined.graphemes(true).count() == 1 {
+                if !self.target[pos - 1].starts_with(&combined) {
+                    self.errors += 1;
+                }
+                *last = combined;
+                return;
+            }
+        }
+
+        let Some(expected) = self.target.get(pos) else {
+            return;
+        };
+        let correct = expected.starts_with(ch);
+        if !correct {
+            self.errors += 1;
+        }
+        let previous = pos.checked_sub(1).map(|i| self.target[i].as_str());
+        self.key_stats.record(previous, expected, correct, latency);
+        self.input.push(Some(ch.to_string()));
+    }
+
+    /// Finishes the current line like space finishes a word, then types the
+    /// next line's indentation automatically.
+    fn handle_enter(&mut self) {
+        self.skip_to("\n");
+
+        let indent = self.target[self.input.len()..]
+            .iter()
+            .take_while(|g| *g == " ")
+            .count();
+        self.input
+            .extend(std::iter::repeat(Some(" ".to_string())).take(indent));
+    }
+
+    /// Types the run of spaces at the cursor, if there is one.
+    fn handle_tab(&mut self) {
+        let spaces = self.target[self.input.len()..]
+            .iter()
+            .take_while(|g| *g == " ")
+            .count();
+        self.input
+            .extend(std::iter::repeat(Some(" ".to_string())).take(spaces));
+    }
+
+    fn handle_space(&mut self) {
+        self.skip_to(" ");
     }
 }
 
 impl Default for App {
     fn default() -> Self {
-        Self::new(15, None)
+        Self::new(15, None, Config::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with(text: &str) -> App {
+        let mut app = App::new(0, None, Config::default());
+        app.target = graphemes(text);
+        app
+    }
+
+    fn code_app_with(text: &str) -> App {
+        let mut app = App::with_code(
+            CodeSource::Snippets(crate::code::Language::Rust),
+            Config::default(),
+        );
+        app.target = graphemes(text);
+        app
+    }
+
+    fn type_str(app: &mut App, text: &str) {
+        for ch in text.chars() {
+            app.handle_char(ch);
+        }
+    }
+
+    fn typed(app: &App) -> Vec<Option<&str>> {
+        app.input().iter().map(|g| g.as_deref()).collect()
+    }
+
+    #[test]
+    fn toggling_one_word_option_keeps_the_other_override_unsaved() {
+        let mut config = Config::default();
+        config.punctuation = true;
+        let overridden = WordOptions {
+            punctuation: false,
+            numbers: false,
+        };
+        let mut app = App::new(5, None, config).with_word_options(overridden);
+        app.set_word_options(WordOptions {
+            numbers: true,
+            ..overridden
+        });
+        assert!(app.config.punctuation);
+        assert!(app.config.numbers);
+        assert!(!app.word_options.punctuation);
+    }
+
+    #[test]
+    fn replaying_looks_up_the_word_list_file() {
+        let record = Record {
+            timestamp: 0,
+            mode: Mode::Words { count: 2 },
+            word_list: "english".to_string(),
+            wpm: 0.0,
+            raw_wpm: 0.0,
+            keystroke_accuracy: None,
+            final_accuracy: 0.0,
+            duration: 0.0,
+            target: "a b".to_string(),
+        };
+        let app = App::replaying(&record, Config::default());
+        assert_eq!(app.word_list, "english.json");
+        assert_eq!(app.results().word_list, "english");
+    }
+
+    #[test]
+    fn replay_moves_forward_and_back() {
+        let key = |ms, position, key| KeyEvent::new(Duration::from_millis(ms), position, key);
+        let events = [
+            key(100, 0, Key::Char('a')),
+            key(200, 1, Key::Char('x')),
+            key(300, 2, Key::Backspace),
+            key(400, 1, Key::Char('b')),
+            key(500, 2, Key::Char('c')),
+        ];
+        let mut app = app_with("abc");
+
+        app.replay(&events, Duration::from_millis(250));
+        assert_eq!(typed(&app), [Some("a"), Some("x")]);
+        app.replay(&events, Duration::from_millis(450));
+        assert_eq!(typed(&app), [Some("a"), Some("b")]);
+        assert_eq!(app.events.len(), 4);
+
+        app.replay(&events, Duration::from_millis(150));
+        assert_eq!(typed(&app), [Some("a")]);
+        assert_eq!(app.events.len(), 1);
+
+        app.replay(&events, Duration::from_millis(500));
+        assert!(app.is_done());
+        assert_eq!(app.keystrokes, 4);
+        assert_eq!(app.events.len(), 5);
+    }
+
+    #[test]
+    fn cyrillic_counts_characters_not_bytes() {
+        let mut app = app_with("привет мир");
+        type_str(&mut app, "привет ми");
+        assert!(!app.is_done());
+        assert_eq!(app.input().len(), 9);
+
+        app.handle_char('р');
+        assert!(app.is_done());
+        assert_eq!(app.char_counts().correct, 10);
+        assert_eq!(app.accuracy(), 100.0);
+    }
+
+    #[test]
+    fn greek_space_skips_rest_of_word() {
+        let mut app = app_with("καλή μέρα");
+        type_str(&mut app, "κα ");
+        assert_eq!(typed(&app), [Some("κ"), Some("α"), None, None, Some(" ")]);
+        assert_eq!(app.char_counts().missed, 2);
+    }
+
+    #[test]
+    fn devanagari_vowel_signs_join_previous_grapheme() {
+        let mut app = app_with("कि");
+        app.handle_char('क');
+        // Still waiting for the vowel sign
+        assert!(!app.is_done());
+
+        app.handle_char('ि');
+        assert_eq!(typed(&app), [Some("कि")]);
+        assert!(app.is_done());
+        assert_eq!(app.accuracy(), 100.0);
+    }
+
+    #[test]
+    fn backspace_takes_grapheme_apart() {
+        let mut app = app_with("किताब");
+        type_str(&mut app, "कि");
+        app.handle_backspace();
+        assert_eq!(typed(&app), [Some("क")]);
+        app.handle_backspace();
+        assert!(app.input().is_empty());
+    }
+
+    #[test]
+    fn amharic_mistakes_are_scored_per_character() {
+        let mut app = app_with("ሰላም ዓለም");
+        type_str(&mut app, "ሰለም ዓለም");
+        assert!(app.is_done());
+
+        let counts = app.char_counts();
+        assert_eq!(counts.correct, 6);
+        assert_eq!(counts.incorrect, 1);
+        assert_eq!(app.errors, 1);
+    }
+
+    #[test]
+    fn emoji_with_modifier_is_one_character() {
+        let mut app = app_with("a👍🏽b");
+        assert_eq!(app.target().len(), 3);
+
+        type_str(&mut app, "a👍🏽b");
+        assert!(app.is_done());
+        assert_eq!(app.char_counts().correct, 3);
+    }
+
+    #[test]
+    fn ctrl_backspace_deletes_multibyte_word() {
+        let mut app = app_with("один два три");
+        type_str(&mut app, "один дв");
+        app.handle_ctrl_backspace();
+        assert_eq!(app.input().len(), 5);
+        assert_eq!(app.input().last(), Some(&Some(" ".to_string())));
+    }
+
+    #[test]
+    fn keystrokes_are_logged_with_positions() {
+        let mut app = app_with("ab cd");
+        type_str(&mut app, "ax");
+        app.handle_backspace();
+        type_str(&mut app, " c");
+        app.handle_ctrl_backspace();
+
+        let log: Vec<(usize, Key)> = app
+            .events
+            .iter()
+            .map(|event| (event.position, event.key))
+            .collect();
+        assert_eq!(
+            log,
+            [
+                (0, Key::Char('a')),
+                (1, Key::Char('x')),
+                (2, Key::Backspace),
+                (1, Key::Skip(' ')),
+                (3, Key::Char('c')),
+                (4, Key::CtrlBackspace),
+            ]
+        );
+        assert!(app.events.windows(2).all(|pair| pair[0].ms <= pair[1].ms));
+    }
+
+    #[test]
+    fn net_wpm_only_counts_correct_words() {
+        let mut app = app_with("ab cd ef");
+        type_str(&mut app, "ab cx ef");
+        // "ab " and "ef" count, "cx " doesn't
+        assert_eq!(app.correct_word_chars(), 5);
+        assert_eq!(app.keystrokes, 8);
+    }
+
+    #[test]
+    fn corrected_mistakes_still_cost_keystroke_accuracy() {
+        let mut app = app_with("ab cd");
+        type_str(&mut app, "ax");
+        app.handle_backspace();
+        type_str(&mut app, "b c");
+        assert_eq!(app.final_accuracy(), 100.0);
+        assert_eq!(app.accuracy(), 80.0);
+
+        // Skipping the rest of a word is a mistake too
+        type_str(&mut app, " ");
+        assert_eq!(app.errors, 2);
+    }
+
+    #[test]
+    fn backspace_after_finishing_keeps_results() {
+        let mut app = app_with("ab");
+        type_str(&mut app, "ab");
+        assert!(app.is_done());
+        let events = app.events.len();
+
+        app.handle_backspace();
+        app.handle_ctrl_backspace();
+        assert!(app.is_done());
+        assert_eq!(typed(&app), [Some("a"), Some("b")]);
+        assert_eq!(app.events.len(), events);
+    }
+
+    #[test]
+    fn last_partial_second_is_snapshotted() {
+        let mut app = app_with("ab cd");
+        type_str(&mut app, "ab cd");
+        assert!(app.is_done());
+
+        // Well under a second, but the keystrokes still show up
+        assert_eq!(app.snapshots.len(), 1);
+        assert_eq!(app.snapshots[0].keystrokes, 5);
+    }
+
+    #[test]
+    fn enter_and_tab_outside_code_mode_do_not_start_the_clock() {
+        let mut app = app_with("ab");
+        app.handle_char('\n');
+        app.handle_char('\t');
+        assert!(app.start_time.is_none());
+
+        app.handle_char('a');
+        assert!(app.start_time.is_some());
+    }
+
+    #[test]
+    fn enter_types_the_next_line_indentation() {
+        let mut app = code_app_with("fn a() {\n    b\n}");
+        type_str(&mut app, "fn a() {\n");
+        assert_eq!(app.input().len(), "fn a() {\n    ".len());
+        type_str(&mut app, "b\n}");
+        assert!(app.is_done());
+        assert_eq!(app.errors, 0);
+        assert_eq!(app.accuracy(), 100.0);
+    }
+
+    #[test]
+    fn enter_mid_line_skips_the_rest_as_an_error() {
+        let mut app = code_app_with("abc\n  d");
+        type_str(&mut app, "a\n");
+        assert_eq!(
+            typed(&app),
+            [Some("a"), None, None, Some("\n"), Some(" "), Some(" ")]
+        );
+        assert_eq!(app.errors, 1);
+        assert_eq!(app.char_counts().missed, 2);
+    }
+
+    #[test]
+    fn tab_jumps_over_a_run_of_spaces() {
+        let mut app = code_app_with("a    b");
+        type_str(&mut app, "a\t");
+        assert_eq!(app.input().len(), 5);
+        // Nothing to jump over
+        app.handle_char('\t');
+        assert_eq!(app.input().len(), 5);
+        app.handle_char('b');
+        assert!(app.is_done());
+        assert_eq!(app.errors, 0);
     }
 }
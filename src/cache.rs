@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::quotes::parse_language;
+use crate::words::{cache_dir, download, fetch_list, WordList};
+
+/// Word list files in the cache, sorted by name.
+fn cached() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(cache_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Accepts a language with or without its `.json` extension, rejecting
+/// anything that could reach outside the cache.
+fn file_name(language: &str) -> Result<String, String> {
+    let language = parse_language(language)?;
+    if language.ends_with(".json") {
+        Ok(language)
+    } else {
+        Ok(format!("{language}.json"))
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Number of words in a cached list, or why it can't be read.
+fn word_count(path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let word_list: WordList = serde_json::from_str(&content)?;
+    Ok(word_list.words.len())
+}
+
+pub fn print_list() {
+    let paths = cached();
+    if paths.is_empty() {
+        println!("No word lists downloaded yet.");
+        return;
+    }
+
+    println!("{:<32}{:>10}{:>9}", "word list", "size", "words");
+    let mut total = 0;
+    for path in paths {
+        let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        total += size;
+        let words = match word_count(&path) {
+            Ok(count) => count.to_string(),
+            Err(_) => "invalid".to_string(),
+        };
+        println!("{:<32}{:>10}{:>9}", name(&path), format_size(size), words);
+    }
+    println!("{:<32}{:>10}", "total", format_size(total));
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes}B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}K", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+pub fn clear() -> Result<(), Box<dyn std::error::Error>> {
+    let paths = cached();
+    for path in &paths {
+        fs::remove_file(path).map_err(|e| format!("failed to remove {}: {e}", path.display()))?;
+    }
+    println!("Removed {} word list(s)", paths.len());
+    Ok(())
+}
+
+pub fn remove(language: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = cache_dir().join(file_name(language)?);
+    if !path.is_file() {
+        return Err(format!("{} isn't downloaded", name(&path)).into());
+    }
+    fs::remove_file(&path)?;
+    println!("Removed {}", name(&path));
+    Ok(())
+}
+
+/// Downloads each language from `sources`, carrying on past failures.
+pub async fn prefetch(
+    languages: &[String],
+    sources: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = 0;
+    for language in languages {
+        let filename = match file_name(language) {
+            Ok(filename) => filename,
+            Err(e) => {
+                failed += 1;
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        match download(&cache_dir(), &filename, sources, |_| {}).await {
+            Ok(words) => println!("{:<32}{:>9} words", name(Path::new(&filename)), words.len()),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{e}");
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} of {} word list(s) failed", languages.len()).into());
+    }
+    Ok(())
+}
+
+/// Checks that every cached list parses, downloading broken ones again. A
+/// broken list is only replaced once a good copy has been downloaded.
+pub async fn verify(sources: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = 0;
+    for path in cached() {
+        let Err(problem) = word_count(&path) else {
+            println!("{:<32}ok", name(&path));
+            continue;
+        };
+
+        println!("{:<32}broken ({problem}), downloading again", name(&path));
+        let filename = path.file_name().unwrap_or_default().to_string_lossy();
+        let content = match fetch_list(&filename, sources, |_| {}).await {
+            Ok(content) => content,
+            Err(e) => {
+                failed += 1;
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, &path)?;
+    }
+
+    if failed > 0 {
+        return Err(format!("{failed} word list(s) couldn't be repaired").into());
+    }
+    Ok(())
+}
@@ -1,9 +1,46 @@
 use clap::{Parser, Subcommand};
 
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use crate::code::Language;
+use crate::passages::{parse_range, ParagraphRange};
+use crate::quotes::{parse_language, QuoteLength};
+use crate::race::DEFAULT_PORT;
+use crate::stats::parse_date;
+
 #[derive(Parser)]
 #[command(name = "tiddy")]
 #[command(about = "A minimal typing test in the terminal")]
 pub struct Cli {
+    /// Start the next test automatically a few seconds after finishing
+    #[arg(long, global = true)]
+    pub auto_restart: bool,
+
+    /// Add capitals and punctuation to generated words
+    #[arg(long, global = true, overrides_with = "no_punctuation")]
+    pub punctuation: bool,
+
+    /// Leave out punctuation even if the config turns it on
+    #[arg(long, global = true, overrides_with = "punctuation")]
+    pub no_punctuation: bool,
+
+    /// Mix numbers into generated words
+    #[arg(long, global = true, overrides_with = "no_numbers")]
+    pub numbers: bool,
+
+    /// Leave out numbers even if the config turns them on
+    #[arg(long, global = true, overrides_with = "numbers")]
+    pub no_numbers: bool,
+
+    /// Race a ghost caret typing at this many words per minute
+    #[arg(long, global = true, value_name = "WPM")]
+    pub pace: Option<u32>,
+
+    /// Race a ghost of your personal best in the same mode
+    #[arg(long, global = true, conflicts_with = "pace")]
+    pub ghost: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -14,26 +51,144 @@ pub enum Commands {
     Words { count: usize },
     /// Test typing for a specific duration
     Time { seconds: usize },
+    /// Test typing with a quote
+    Quote {
+        /// Only pick quotes from this length group
+        #[arg(short, long)]
+        length: Option<QuoteLength>,
+        /// Language of the quote collection
+        #[arg(long, default_value = "english", value_parser = parse_language)]
+        language: String,
+    },
+    /// Test typing with text from a file, one paragraph at a time
+    File {
+        path: PathBuf,
+        /// Only type these paragraphs, e.g. 3, 3- or 3-7
+        #[arg(short, long, value_parser = parse_range)]
+        paragraphs: Option<ParagraphRange>,
+    },
+    /// Test typing with text piped through stdin
+    #[command(name = "-")]
+    Stdin {
+        /// Only type these paragraphs, e.g. 3, 3- or 3-7
+        #[arg(short, long, value_parser = parse_range)]
+        paragraphs: Option<ParagraphRange>,
+    },
+    /// Test typing source code, keeping line breaks and indentation
+    Code {
+        /// Source file to take snippets from
+        path: Option<PathBuf>,
+        /// Use a built-in snippet in this language instead of a file
+        #[arg(short, long, conflicts_with = "path")]
+        language: Option<Language>,
+        /// Number of lines per snippet taken from a file
+        #[arg(long, default_value_t = 10)]
+        lines: usize,
+    },
+    /// Practice words full of the keys you miss or hesitate on most
+    Practice {
+        #[arg(default_value_t = 25)]
+        count: usize,
+    },
+    /// Manage color themes
+    Themes {
+        #[command(subcommand)]
+        command: ThemesCommand,
+    },
+    /// List the word lists that can be picked
+    Languages {
+        /// Fetch the latest list of word lists from upstream first
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Manage downloaded word lists
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Summarize results of past tests
+    Stats {
+        /// Only include tests typed with this word list
+        #[arg(long)]
+        word_list: Option<String>,
+        /// Only include tests from this date on (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        since: Option<u64>,
+        /// Only include tests up to and including this date (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        until: Option<u64>,
+    },
+    /// Host a race that others can join over the network
+    Host {
+        /// Address to listen on; 0.0.0.0 lets other machines join
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: IpAddr,
+        /// Port to listen on
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        /// Number of words to race on
+        #[arg(long, default_value_t = 25)]
+        words: usize,
+        /// Name shown to other racers
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Join a race started with `tiddy host`
+    Join {
+        /// Host address, like 192.168.1.20 or localhost:7878
+        addr: String,
+        /// Name shown to other racers
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Play back a finished test keystroke by keystroke
+    Replay {
+        /// Replay id from --list, or a replay file; the latest test if omitted
+        replay: Option<String>,
+        /// List recorded replays
+        #[arg(long, conflicts_with = "replay")]
+        list: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ThemesCommand {
+    /// List every available theme
+    List,
+    /// Import themes from monkeytype (all of them if no names are given)
+    Import { names: Vec<String> },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List downloaded word lists with their size and word count
+    List,
+    /// Delete every downloaded word list
+    Clear,
+    /// Delete one downloaded word list
+    Remove { language: String },
+    /// Download word lists ahead of time
+    Prefetch {
+        #[arg(required = true)]
+        languages: Vec<String>,
+    },
+    /// Check every downloaded word list and download broken ones again
+    Verify,
 }
 
 impl Commands {
     pub fn word_count(&self) -> usize {
         match self {
-            Commands::Words { count } => *count,
+            Commands::Words { count } | Commands::Practice { count } => *count,
             Commands::Time { .. } => 100, // Default word count for time mode
+            _ => 15,
         }
     }
 
     pub fn time_limit(&self) -> Option<usize> {
         match self {
-            Commands::Words { .. } => None,
             Commands::Time { seconds } => Some(*seconds),
+            _ => None,
         }
     }
 }
-
-impl Default for Commands {
-    fn default() -> Self {
-        Commands::Words { count: 15 }
-    }
-}
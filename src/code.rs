@@ -0,0 +1,240 @@
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fs;
+use std::path::Path;
+
+const TAB_WIDTH: usize = 4;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    Rust,
+    Python,
+    Javascript,
+    Go,
+    C,
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{self:?}").to_lowercase();
+        write!(f, "{name}")
+    }
+}
+
+impl Language {
+    fn snippets(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                r#"fn main() {
+    let words: Vec<&str> = "the quick brown fox".split(' ').collect();
+    for (i, word) in words.iter().enumerate() {
+        println!("{i}: {word}");
+    }
+}"#,
+                r#"impl Iterator for Counter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.count < 5 {
+            self.count += 1;
+            Some(self.count)
+        } else {
+            None
+        }
+    }
+}"#,
+                r#"match std::fs::read_to_string(&path) {
+    Ok(content) => content.lines().count(),
+    Err(e) if e.kind() == ErrorKind::NotFound => 0,
+    Err(e) => return Err(e.into()),
+}"#,
+            ],
+            Language::Python => &[
+                r#"def fib(n):
+    a, b = 0, 1
+    for _ in range(n):
+        a, b = b, a + b
+    return a"#,
+                r#"with open("words.txt") as f:
+    counts = {}
+    for line in f:
+        for word in line.split():
+            counts[word] = counts.get(word, 0) + 1
+print(sorted(counts.items(), key=lambda kv: -kv[1])[:10])"#,
+                r#"class Stack:
+    def __init__(self):
+        self.items = []
+
+    def push(self, item):
+        self.items.append(item)
+
+    def pop(self):
+        return self.items.pop() if self.items else None"#,
+            ],
+            Language::Javascript => &[
+                r#"const debounce = (fn, ms) => {
+    let timer;
+    return (...args) => {
+        clearTimeout(timer);
+        timer = setTimeout(() => fn(...args), ms);
+    };
+};"#,
+                r#"async function fetchJson(url) {
+    const res = await fetch(url);
+    if (!res.ok) {
+        throw new Error(`HTTP ${res.status}`);
+    }
+    return res.json();
+}"#,
+                r#"const total = items
+    .filter((item) => item.price > 0)
+    .map((item) => item.price * item.qty)
+    .reduce((sum, x) => sum + x, 0);"#,
+            ],
+            Language::Go => &[
+                r#"func main() {
+    ch := make(chan int)
+    go func() {
+        for i := 0; i < 3; i++ {
+            ch <- i
+        }
+        close(ch)
+    }()
+    for v := range ch {
+        fmt.Println(v)
+    }
+}"#,
+                r#"type Point struct {
+    X, Y float64
+}
+
+func (p Point) Dist(q Point) float64 {
+    return math.Hypot(q.X-p.X, q.Y-p.Y)
+}"#,
+            ],
+            Language::C => &[
+                r#"int main(int argc, char **argv) {
+    for (int i = 1; i < argc; i++) {
+        printf("%d: %s\n", i, argv[i]);
+    }
+    return 0;
+}"#,
+                r#"size_t strlen(const char *s) {
+    const char *p = s;
+    while (*p) {
+        p++;
+    }
+    return p - s;
+}"#,
+            ],
+        }
+    }
+}
+
+/// Expands tabs, strips trailing whitespace and removes the indentation
+/// shared by every line.
+fn normalize(lines: &[&str]) -> String {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            line.replace('\t', &" ".repeat(TAB_WIDTH))
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Where code snippets come from.
+#[derive(Debug, Clone)]
+pub enum CodeSource {
+    Snippets(Language),
+    /// Lines of a source file, typed `window` lines at a time
+    File {
+        name: String,
+        lines: Vec<String>,
+        window: usize,
+    },
+}
+
+impl CodeSource {
+    pub fn from_file(path: &Path, window: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+        if lines.iter().all(|line| line.trim().is_empty()) {
+            return Err(format!("{} is empty", path.display()).into());
+        }
+
+        Ok(CodeSource::File {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            lines,
+            window: window.max(1),
+        })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            CodeSource::Snippets(language) => language.to_string(),
+            CodeSource::File { name, .. } => name.clone(),
+        }
+    }
+
+    pub fn choose(&self) -> String {
+        match self {
+            CodeSource::Snippets(language) => {
+                let snippet = language
+                    .snippets()
+                    .choose(&mut rand::thread_rng())
+                    .copied()
+                    .unwrap_or_default();
+                normalize(&snippet.lines().collect::<Vec<_>>())
+            }
+            CodeSource::File { lines, window, .. } => {
+                // Start the window on a line with code so it doesn't open with blanks
+                let starts: Vec<usize> = (0..lines.len())
+                    .filter(|&i| !lines[i].trim().is_empty())
+                    .collect();
+                let start = starts[rand::thread_rng().gen_range(0..starts.len())];
+                let end = (start + window).min(lines.len());
+                let mut chunk: Vec<&str> = lines[start..end].iter().map(|s| s.as_str()).collect();
+                while chunk.last().is_some_and(|line| line.trim().is_empty()) {
+                    chunk.pop();
+                }
+                normalize(&chunk)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_shared_indentation_with_tabs_expanded() {
+        let lines = ["\t\tif x {", "\t\t\ty();  ", "", "        }"];
+        assert_eq!(normalize(&lines), "if x {\n    y();\n\n}");
+    }
+
+    #[test]
+    fn keeps_indentation_when_a_line_has_none() {
+        let lines = ["fn a() {", "\tb", "}"];
+        assert_eq!(normalize(&lines), "fn a() {\n    b\n}");
+    }
+}
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::Commands;
+use crate::words::{monkeytype_url, DEFAULT_WORD_LIST_REF, LANGUAGE_INDEX_URL};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultMode {
+    Words,
+    Time,
+    Quote,
+}
+
+/// Settings stored in `config.toml`. Missing keys fall back to their defaults.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub color_scheme: String,
+    pub cursor_style: String,
+    pub word_list: String,
+    /// Mix punctuation into generated words
+    pub punctuation: bool,
+    /// Mix numbers into generated words
+    pub numbers: bool,
+    /// Mode used when tiddy is run without a subcommand
+    pub mode: DefaultMode,
+    pub words: usize,
+    pub time: usize,
+    /// Where `tiddy languages --refresh` fetches the list of word lists
+    pub languages_url: String,
+    /// Base URL word lists are downloaded from, instead of monkeytype's
+    /// GitHub repository. `file://` URLs read from a local directory.
+    pub word_list_url: Option<String>,
+    /// Branch, tag or commit of monkeytype's repository to download from
+    pub word_list_ref: String,
+    /// Base URLs tried in order when the main one fails
+    pub word_list_mirrors: Vec<String>,
+    /// Set when `config.toml` couldn't be parsed, so the defaults standing in
+    /// for it are never saved over the user's file
+    #[serde(skip)]
+    read_only: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            color_scheme: "gruvbox".to_string(),
+            cursor_style: "underline".to_string(),
+            word_list: "english.json".to_string(),
+            punctuation: false,
+            numbers: false,
+            mode: DefaultMode::Words,
+            words: 15,
+            time: 30,
+            languages_url: LANGUAGE_INDEX_URL.to_string(),
+            word_list_url: None,
+            word_list_ref: DEFAULT_WORD_LIST_REF.to_string(),
+            word_list_mirrors: Vec::new(),
+            read_only: false,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.toml`. A broken file gives a warning and the defaults,
+    /// which are then only used for this session.
+    pub fn load() -> Self {
+        Self::load_from(&config_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))
+            });
+        match parsed {
+            Ok(config) => config,
+            Err(_) if !path.exists() => Self::default(),
+            Err(e) => {
+                eprintln!("warning: ignoring config: {e}");
+                Self {
+                    read_only: true,
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&config_path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if self.read_only {
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Base URLs to download word lists from, in the order to try them.
+    /// `TIDDY_WORDLIST_URL` takes the place of the configured main URL.
+    pub fn word_list_sources(&self) -> Vec<String> {
+        let main = std::env::var("TIDDY_WORDLIST_URL")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .or_else(|| self.word_list_url.clone())
+            .unwrap_or_else(|| monkeytype_url(&self.word_list_ref));
+
+        std::iter::once(main)
+            .chain(self.word_list_mirrors.iter().cloned())
+            .collect()
+    }
+
+    pub fn default_command(&self) -> Commands {
+        match self.mode {
+            DefaultMode::Words => Commands::Words { count: self.words },
+            DefaultMode::Time => Commands::Time { seconds: self.time },
+            DefaultMode::Quote => Commands::Quote {
+                length: None,
+                language: "english".to_string(),
+            },
+        }
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("tiddy")
+}
+
+fn config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broken_config_is_never_saved_over() {
+        let dir = std::env::temp_dir().join(format!("tiddy-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let broken = "color_scheme = \"nord\"\nwords = \"lots\"\n";
+        fs::write(&path, broken).unwrap();
+
+        let mut config = Config::load_from(&path);
+        assert_eq!(config.words, Config::default().words);
+        config.punctuation = true;
+        config.save_to(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), broken);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::app::Mode;
+use crate::keylog::KeyEvent;
+use crate::replay;
+
+/// A second caret to race against, timed from the first keystroke.
+#[derive(Debug, Clone)]
+pub enum Ghost {
+    /// Types at a steady speed in words per minute
+    Pace(f64),
+    /// Follows the cursor of a recorded test
+    Timeline {
+        events: Vec<KeyEvent>,
+        /// Where the recorded test ended
+        end: usize,
+    },
+}
+
+impl Ghost {
+    /// The fastest recorded test in `mode`, if there is a replay of it.
+    pub fn personal_best(mode: Mode) -> Option<Self> {
+        let best = replay::list()
+            .into_iter()
+            .filter(|replay| replay.record.mode == mode)
+            .max_by(|a, b| a.record.wpm.total_cmp(&b.record.wpm))?;
+
+        Some(Ghost::Timeline {
+            end: best.record.target.graphemes(true).count(),
+            events: best.events,
+        })
+    }
+
+    /// Grapheme position of the ghost after `elapsed`.
+    pub fn position(&self, elapsed: Duration) -> usize {
+        match self {
+            Ghost::Pace(wpm) => (wpm * 5.0 * elapsed.as_secs_f64() / 60.0) as usize,
+            // The cursor sits where the next keystroke will happen
+            Ghost::Timeline { events, end } => events
+                .iter()
+                .find(|event| event.elapsed() > elapsed)
+                .map_or(*end, |event| event.position),
+        }
+    }
+}
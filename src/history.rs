@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::app::Mode;
+
+/// A finished test, stored as one line of `history.jsonl`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Record {
+    /// Seconds since the unix epoch
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub mode: Mode,
+    pub word_list: String,
+    pub wpm: f64,
+    pub raw_wpm: f64,
+    /// Accuracy over every keystroke, counting corrected mistakes. Missing
+    /// from records written before it was tracked.
+    #[serde(default)]
+    pub keystroke_accuracy: Option<f64>,
+    /// Accuracy of the text as it was left, stored as `accuracy` by older
+    /// versions
+    #[serde(alias = "accuracy")]
+    pub final_accuracy: f64,
+    pub duration: f64,
+    pub target: String,
+}
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("tiddy")
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn append(record: &Record) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(data_dir())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Reads every record, oldest first. Lines that fail to parse are skipped.
+pub fn load() -> Vec<Record> {
+    fs::read_to_string(history_path())
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_records_keep_their_accuracy_as_final_accuracy() {
+        let line = r#"{"timestamp":0,"mode":"words","count":10,"word_list":"english","wpm":60.0,"raw_wpm":65.0,"accuracy":95.0,"duration":10.0,"target":""}"#;
+        let record: Record = serde_json::from_str(line).unwrap();
+        assert_eq!(record.final_accuracy, 95.0);
+        assert_eq!(record.keystroke_accuracy, None);
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What a keystroke did to the input.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Key {
+    /// A typed character, including Tab in code mode
+    Char(char),
+    /// Space (or Enter in code mode), which finishes the word or line and
+    /// skips whatever is left of it
+    Skip(char),
+    Backspace,
+    CtrlBackspace,
+}
+
+/// One keystroke of a test.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KeyEvent {
+    /// Milliseconds since the first keystroke of the test
+    pub ms: u64,
+    /// Cursor position, in graphemes, before the keystroke
+    pub position: usize,
+    pub key: Key,
+}
+
+impl KeyEvent {
+    pub fn new(elapsed: Duration, position: usize, key: Key) -> Self {
+        Self {
+            ms: elapsed.as_millis() as u64,
+            position,
+            key,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.ms)
+    }
+}
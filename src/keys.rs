@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::history::data_dir;
+
+/// Gaps longer than this are pauses, not the time it takes to find a key.
+const MAX_LATENCY: Duration = Duration::from_secs(2);
+
+/// Keys seen fewer times than this are too noisy to call weak.
+const MIN_SAMPLES: u32 = 10;
+
+/// How often one key (or pair of keys) was typed, missed, and how long it took.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct KeyStat {
+    pub hits: u32,
+    pub misses: u32,
+    /// Sum of the timed keystrokes' latencies in milliseconds
+    pub total_ms: u64,
+    pub timed: u32,
+}
+
+impl KeyStat {
+    fn samples(&self) -> u32 {
+        self.hits + self.misses
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        self.misses as f64 / self.samples().max(1) as f64
+    }
+
+    pub fn average_ms(&self) -> Option<f64> {
+        (self.timed > 0).then(|| self.total_ms as f64 / self.timed as f64)
+    }
+
+    fn add(&mut self, other: &KeyStat) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.total_ms += other.total_ms;
+        self.timed += other.timed;
+    }
+}
+
+/// Per-character and per-bigram statistics, kept in `keys.json` across tests.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct KeyStats {
+    pub chars: HashMap<String, KeyStat>,
+    pub bigrams: HashMap<String, KeyStat>,
+}
+
+fn keys_path() -> PathBuf {
+    data_dir().join("keys.json")
+}
+
+impl KeyStats {
+    pub fn load() -> Self {
+        fs::read_to_string(keys_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Adds one keystroke aimed at `expected`, which followed `previous` in
+    /// the text. `latency` is the time since the keystroke before it.
+    pub fn record(
+        &mut self,
+        previous: Option<&str>,
+        expected: &str,
+        correct: bool,
+        latency: Option<Duration>,
+    ) {
+        let mut stat = KeyStat::default();
+        if correct {
+            stat.hits = 1;
+        } else {
+            stat.misses = 1;
+        }
+        if let Some(latency) = latency.filter(|latency| *latency <= MAX_LATENCY) {
+            stat.total_ms = latency.as_millis() as u64;
+            stat.timed = 1;
+        }
+
+        self.chars
+            .entry(expected.to_string())
+            .or_default()
+            .add(&stat);
+        if let Some(previous) = previous.filter(|previous| !previous.trim().is_empty()) {
+            self.bigrams
+                .entry(format!("{previous}{expected}"))
+                .or_default()
+                .add(&stat);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// Folds a finished test into the stored totals.
+    pub fn append(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stored = Self::load();
+        for (key, stat) in &self.chars {
+            stored.chars.entry(key.clone()).or_default().add(stat);
+        }
+        for (key, stat) in &self.bigrams {
+            stored.bigrams.entry(key.clone()).or_default().add(stat);
+        }
+
+        fs::create_dir_all(data_dir())?;
+        fs::write(keys_path(), serde_json::to_string(&stored)?)?;
+        Ok(())
+    }
+
+    /// Characters ordered from weakest, with their weakness scores.
+    pub fn weakest(&self) -> Vec<(String, f64)> {
+        let weaknesses = Weaknesses::new(self);
+        let mut keys: Vec<(String, f64)> = weaknesses.chars.into_iter().collect();
+        keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+        keys
+    }
+}
+
+/// How much worse than average each key is: 1.0 is average, 2.0 means twice
+/// the errors or twice the latency. Keys with too few samples count as average.
+#[derive(Debug, Default)]
+pub struct Weaknesses {
+    chars: HashMap<String, f64>,
+    bigrams: HashMap<String, f64>,
+}
+
+impl Weaknesses {
+    pub fn new(stats: &KeyStats) -> Self {
+        Self {
+            chars: scores(&stats.chars),
+            bigrams: scores(&stats.bigrams),
+        }
+    }
+
+    /// Sampling weight for a word: 1 plus how far each of its letters and
+    /// bigrams is above average, so words full of weak spots come up most.
+    pub fn weight(&self, word: &str) -> f64 {
+        let chars: Vec<String> = word.graphemes(true).map(str::to_string).collect();
+        let excess = |score: Option<&f64>| score.map_or(0.0, |score| (score - 1.0).max(0.0));
+
+        let char_excess: f64 = chars.iter().map(|ch| excess(self.chars.get(ch))).sum();
+        let bigram_excess: f64 = chars
+            .windows(2)
+            .map(|pair| excess(self.bigrams.get(&pair.concat())))
+            .sum();
+        1.0 + char_excess + bigram_excess
+    }
+}
+
+fn scores(stats: &HashMap<String, KeyStat>) -> HashMap<String, f64> {
+    let reliable: Vec<&KeyStat> = stats
+        .values()
+        .filter(|stat| stat.samples() >= MIN_SAMPLES)
+        .collect();
+    if reliable.is_empty() {
+        return HashMap::new();
+    }
+
+    let total = |f: fn(&KeyStat) -> u64| reliable.iter().map(|stat| f(stat)).sum::<u64>() as f64;
+    let error_rate = total(|s| s.misses as u64) / total(|s| s.samples() as u64);
+    let average_ms = total(|s| s.total_ms) / total(|s| s.timed as u64).max(1.0);
+
+    stats
+        .iter()
+        .filter(|(_, stat)| stat.samples() >= MIN_SAMPLES)
+        .map(|(key, stat)| {
+            // Smoothed so a clean history doesn't divide by zero
+            let errors = (stat.error_rate() + 0.01) / (error_rate + 0.01);
+            let latency = match stat.average_ms() {
+                Some(ms) if average_ms > 0.0 => ms / average_ms,
+                _ => 1.0,
+            };
+            (key.clone(), (errors + latency) / 2.0)
+        })
+        .collect()
+}
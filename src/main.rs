@@ -1,6 +1,20 @@
 mod app;
+mod cache;
 mod cli;
+mod code;
+mod config;
+mod ghost;
+mod history;
+mod keylog;
+mod keys;
+mod passages;
 mod popup;
+mod punctuation;
+mod quotes;
+mod race;
+mod replay;
+mod stats;
+mod themes;
 mod ui;
 mod words;
 
@@ -14,21 +28,133 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use app::App;
-use cli::Cli;
+use cli::{CacheCommand, Cli, Commands, ThemesCommand};
+use code::{CodeSource, Language};
+use config::Config;
+use ghost::Ghost;
+use passages::Passages;
+use punctuation::WordOptions;
+use race::{Race, Role};
+use replay::Replay;
+use words::generate_words;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
-    let command = cli.command.unwrap_or_default();
-    let word_count = command.word_count();
-    let time_limit = command.time_limit();
+    // A broken config shouldn't keep anyone from typing
+    let config = Config::load();
+    let command = cli.command.unwrap_or_else(|| config.default_command());
+    // Commands that only print to stdout run without taking over the terminal
+    if let Commands::Stats {
+        word_list,
+        since,
+        until,
+    } = command
+    {
+        stats::print(&stats::Filter {
+            word_list,
+            since,
+            until,
+        });
+        return Ok(());
+    }
+
+    if let Commands::Themes { command } = command {
+        match command {
+            ThemesCommand::List => {
+                for theme in themes::load() {
+                    println!("{theme}");
+                }
+            }
+            ThemesCommand::Import { names } => {
+                let imported = themes::import(&names).await?;
+                println!("Imported {} theme(s)", imported.len());
+            }
+        }
+        return Ok(());
+    }
+
+    if let Commands::Cache { command } = command {
+        let sources = config.word_list_sources();
+        match command {
+            CacheCommand::List => cache::print_list(),
+            CacheCommand::Clear => cache::clear()?,
+            CacheCommand::Remove { language } => cache::remove(&language)?,
+            CacheCommand::Prefetch { languages } => cache::prefetch(&languages, &sources).await?,
+            CacheCommand::Verify => cache::verify(&sources).await?,
+        }
+        return Ok(());
+    }
+
+    if let Commands::Languages { refresh } = command {
+        if refresh {
+            let count = words::refresh_languages(&config.languages_url).await?;
+            println!("Found {count} word lists upstream");
+        }
+        for language in words::languages() {
+            println!("{}", language.trim_end_matches(".json"));
+        }
+        return Ok(());
+    }
+
+    if let Commands::Replay { list: true, .. } = command {
+        replay::print_list();
+        return Ok(());
+    }
+    // Flags override the config either way for this run without saving it
+    let options = WordOptions {
+        punctuation: (cli.punctuation || config.punctuation) && !cli.no_punctuation,
+        numbers: (cli.numbers || config.numbers) && !cli.no_numbers,
+    };
+    // Everything that can fail is set up before taking over the terminal so
+    // errors print normally
+    let session = match &command {
+        Commands::Replay { replay, .. } => {
+            let replay = replay::load(replay.as_deref())?;
+            Session::Replay(App::replaying(&replay.record, config), replay)
+        }
+        Commands::Host {
+            bind,
+            port,
+            words,
+            name,
+        } => {
+            let words = generate_words(*words, Some(&config.word_list));
+            let text = punctuation::apply(words, options).join(" ");
+            let role = Role::Host {
+                bind: *bind,
+                port: *port,
+                text,
+            };
+            let name = name.clone().unwrap_or_else(race::default_name);
+            Session::Race(race::connect(role, name, config).await?)
+        }
+        Commands::Join { addr, name } => {
+            let role = Role::Join { addr: addr.clone() };
+            let name = name.clone().unwrap_or_else(race::default_name);
+            Session::Race(race::connect(role, name, config).await?)
+        }
+        _ => {
+            let app = typing_app(&command, config, options).await?;
+            let ghost = match cli.pace {
+                Some(wpm) => Some(Ghost::Pace(wpm as f64)),
+                None if cli.ghost => Ghost::personal_best(app.mode()),
+                None => None,
+            };
+            Session::Test(app.with_ghost(ghost))
+        }
+    };
 
     // Setup terminal
     terminal::enable_raw_mode()?;
     io::stdout().execute(terminal::EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
-    let result = run_typing_test(&mut terminal, word_count, time_limit).await;
+    let result = match session {
+        Session::Test(app) => run_typing_test(&mut terminal, app, cli.auto_restart).await,
+        Session::Replay(app, replay) => replay::play(&mut terminal, app, &replay),
+        Session::Race(race) => race.run(&mut terminal).await,
+    };
 
     // Cleanup terminal
     io::stdout().execute(terminal::LeaveAlternateScreen)?;
@@ -37,12 +163,54 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     result
 }
 
+/// What runs once the terminal has been taken over.
+enum Session {
+    Test(App),
+    Replay(App, Replay),
+    Race(Race),
+}
+
+async fn typing_app(
+    command: &Commands,
+    config: Config,
+    options: WordOptions,
+) -> Result<App, Box<dyn std::error::Error>> {
+    Ok(match command {
+        Commands::Quote { length, language } => {
+            let quotes = quotes::download(language, *length)
+                .await
+                .map_err(|e| format!("failed to load {language} quotes: {e}"))?;
+            App::with_quotes(quotes, config)
+        }
+        Commands::File { path, paragraphs } => {
+            App::with_passages(Passages::from_file(path, *paragraphs)?, config)
+        }
+        Commands::Stdin { paragraphs } => {
+            App::with_passages(Passages::from_stdin(*paragraphs)?, config)
+        }
+        Commands::Code {
+            path: Some(path),
+            lines,
+            ..
+        } => App::with_code(CodeSource::from_file(path, *lines)?, config),
+        Commands::Code { language, .. } => App::with_code(
+            CodeSource::Snippets(language.unwrap_or(Language::Rust)),
+            config,
+        ),
+        Commands::Practice { count } => {
+            App::with_practice(*count, config).with_word_options(options)
+        }
+        _ => {
+            App::new(command.word_count(), command.time_limit(), config).with_word_options(options)
+        }
+    })
+}
+
 async fn run_typing_test(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    word_count: usize,
-    time_limit: Option<usize>,
+    mut app: App,
+    auto_restart: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut app = App::new(word_count, time_limit);
     let mut restart_timer: Option<std::time::Instant> = None;
 
     loop {
@@ -58,25 +226,45 @@ async fn run_typing_test(
             None
         };
 
+        app.tick();
+
+        app.save_finished();
+
         terminal.draw(|frame| {
-            ui::render_typing_test(
-                frame,
-                ui::RenderConfig {
-                    target: app.target(),
-                    input: app.input(),
-                    wpm: app.wpm(),
-                    accuracy: app.accuracy(),
-                    scheme_index: app.scheme_index,
-                    cursor_style_index: app.cursor_style_index,
-                    is_done: app.is_done(),
-                    restart_countdown: countdown,
-                },
-            );
+            if app.is_done() {
+                ui::render_results(
+                    frame,
+                    ui::ResultsConfig {
+                        results: &app.results(),
+                        theme: app.theme(),
+                        restart_countdown: countdown,
+                        quote: app.quote(),
+                    },
+                );
+            } else {
+                ui::render_typing_test(
+                    frame,
+                    ui::RenderConfig {
+                        target: app.target(),
+                        input: app.input(),
+                        net_wpm: app.net_wpm(),
+                        raw_wpm: app.raw_wpm(),
+                        accuracy: app.accuracy(),
+                        consistency: app.consistency(),
+                        theme: app.theme(),
+                        cursor_style_index: app.cursor_style_index,
+                        right_to_left: app.is_right_to_left(),
+                        ghost: app.ghost_position(),
+                        racers: &[],
+                        help: "Ctrl+R restart | Esc quit",
+                    },
+                );
+            }
 
             app.popup_manager.render(frame, frame.size());
         })?;
 
-        if app.is_done() && restart_timer.is_none() {
+        if auto_restart && app.is_done() && restart_timer.is_none() {
             restart_timer = Some(std::time::Instant::now());
         }
 
@@ -91,7 +279,7 @@ async fn run_typing_test(
 
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
-                if app.handle_popup_key(key.code).await {
+                if app.handle_popup_key(key.code) {
                     continue;
                 }
 
@@ -104,18 +292,11 @@ async fn run_typing_test(
                         app.restart();
                         restart_timer = None;
                     }
-                    (KeyCode::Char('h'), KeyModifiers::CONTROL) => app.handle_ctrl_backspace(), // Ctrl+Backspace in Ubuntu
-                    (KeyCode::Char('w'), KeyModifiers::CONTROL) => app.handle_ctrl_backspace(),
-                    (KeyCode::Backspace, KeyModifiers::CONTROL) => app.handle_ctrl_backspace(),
-                    (KeyCode::Delete, KeyModifiers::CONTROL) => app.handle_ctrl_backspace(),
-                    (KeyCode::Backspace, _) => app.handle_backspace(),
-                    (KeyCode::Char(ch), KeyModifiers::NONE) => {
-                        if app.is_done() {
-                            restart_timer = None;
-                        }
-                        app.handle_char(ch);
+                    (KeyCode::Enter, _) if app.is_done() => {
+                        app.restart();
+                        restart_timer = None;
                     }
-                    _ => {}
+                    (code, modifiers) => app.handle_typing_key(code, modifiers),
                 }
             }
         }
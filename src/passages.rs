@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::history::data_dir;
+
+/// A 1-based, inclusive range of paragraphs such as `3-7`, `3-` or `5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParagraphRange {
+    start: usize,
+    end: Option<usize>,
+}
+
+pub fn parse_range(range: &str) -> Result<ParagraphRange, String> {
+    let invalid = || format!("invalid paragraph range `{range}`, expected N, N- or N-M");
+    let number = |s: &str| s.trim().parse::<usize>().ok().filter(|&n| n > 0);
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, "")) => (number(start).ok_or_else(invalid)?, None),
+        Some((start, end)) => (
+            number(start).ok_or_else(invalid)?,
+            Some(number(end).ok_or_else(invalid)?),
+        ),
+        None => {
+            let n = number(range).ok_or_else(invalid)?;
+            (n, Some(n))
+        }
+    };
+
+    if end.is_some_and(|end| end < start) {
+        return Err(invalid());
+    }
+    Ok(ParagraphRange { start, end })
+}
+
+/// Splits text into paragraphs on blank lines, collapsing all other
+/// whitespace (including line breaks) into single spaces.
+fn paragraphs(text: &str) -> Vec<String> {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.extend(line.split_whitespace());
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+
+    paragraphs
+}
+
+fn positions_path() -> PathBuf {
+    data_dir().join("positions.json")
+}
+
+fn load_positions() -> HashMap<String, usize> {
+    fs::read_to_string(positions_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// User-supplied text, typed one paragraph per test. For files the current
+/// paragraph is remembered between sessions.
+#[derive(Debug, Clone)]
+pub struct Passages {
+    name: String,
+    paragraphs: Vec<String>,
+    start: usize,
+    current: usize,
+    /// Key in `positions.json`, absent for stdin
+    key: Option<String>,
+}
+
+impl Passages {
+    pub fn from_file(
+        path: &Path,
+        range: Option<ParagraphRange>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let key = fs::canonicalize(path)?.to_string_lossy().to_string();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut passages = Self::new(name, &content, range)?;
+        // Resume where the last session stopped, if that's inside the range
+        if let Some(&saved) = load_positions().get(&key) {
+            if (passages.start..passages.paragraphs.len()).contains(&saved) {
+                passages.current = saved;
+            }
+        }
+        passages.key = Some(key);
+        Ok(passages)
+    }
+
+    pub fn from_stdin(range: Option<ParagraphRange>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        Self::new("stdin".to_string(), &content, range)
+    }
+
+    fn new(
+        name: String,
+        text: &str,
+        range: Option<ParagraphRange>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut paragraphs = paragraphs(text);
+        let total = paragraphs.len();
+        let start = range.map_or(0, |range| range.start - 1);
+        if let Some(end) = range.and_then(|range| range.end) {
+            paragraphs.truncate(end);
+        }
+
+        if start >= paragraphs.len() {
+            return Err(format!("{name} has {total} paragraph(s), nothing to type").into());
+        }
+
+        Ok(Self {
+            name,
+            paragraphs,
+            start,
+            current: start,
+            key: None,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn current(&self) -> &str {
+        &self.paragraphs[self.current]
+    }
+
+    /// Moves on to the next paragraph, wrapping around at the end of the range,
+    /// and remembers it for the next session.
+    pub fn advance(&mut self) {
+        self.current += 1;
+        if self.current >= self.paragraphs.len() {
+            self.current = self.start;
+        }
+
+        if let Some(key) = &self.key {
+            let mut positions = load_positions();
+            positions.insert(key.clone(), self.current);
+            // Losing the position only means starting over next time
+            let _ = fs::create_dir_all(data_dir()).and_then(|_| {
+                fs::write(
+                    positions_path(),
+                    serde_json::to_string_pretty(&positions).unwrap_or_default(),
+                )
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: usize, end: Option<usize>) -> ParagraphRange {
+        ParagraphRange { start, end }
+    }
+
+    #[test]
+    fn parses_single_open_and_closed_ranges() {
+        assert_eq!(parse_range("5"), Ok(range(5, Some(5))));
+        assert_eq!(parse_range("3-"), Ok(range(3, None)));
+        assert_eq!(parse_range("3-7"), Ok(range(3, Some(7))));
+        assert_eq!(parse_range(" 3 - 7 "), Ok(range(3, Some(7))));
+        assert_eq!(parse_range("4-4"), Ok(range(4, Some(4))));
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        for bad in ["0", "0-3", "3-0", "7-3", "-3", "", "a", "3-x", "1-2-3"] {
+            assert!(parse_range(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn splits_paragraphs_on_blank_lines() {
+        let text = "\n\nOne\r\nline two\r\n\r\n  \n\n\nTwo  words\n \t\nThree\r\n";
+        assert_eq!(paragraphs(text), ["One line two", "Two words", "Three"]);
+        assert!(paragraphs(" \n\n \r\n").is_empty());
+    }
+}
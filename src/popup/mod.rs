@@ -8,7 +8,8 @@ use ratatui::{
     Frame,
 };
 
-use crate::words::{downloaded, languages};
+use crate::punctuation::WordOptions;
+use crate::words::{downloaded, languages, Progress};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PopupAction {
@@ -17,15 +18,30 @@ pub enum PopupAction {
     SelectWordList(String),
     SelectColorScheme(usize),
     SelectCursorStyle(usize),
+    SetWordOptions(WordOptions),
+    CancelDownload,
 }
 
+/// A word list download, shown on the list's entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadStatus {
+    Running { name: String, progress: Progress },
+    Failed { name: String },
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Section {
     WordList,
     ColorScheme,
     CursorStyle,
+    WordOptions,
 }
 
+/// Toggles shown in the options section, in order.
+const WORD_OPTIONS: [&str; 2] = ["punctuation", "numbers"];
+
 trait Filterable {
     fn filter(&self, query: &str) -> Vec<String>;
 }
@@ -43,10 +59,11 @@ impl Filterable for Vec<String> {
 }
 
 impl Section {
-    const ALL: [Section; 3] = [
+    const ALL: [Section; 4] = [
         Section::WordList,
         Section::ColorScheme,
         Section::CursorStyle,
+        Section::WordOptions,
     ];
 }
 
@@ -57,15 +74,27 @@ pub struct PopupManager {
     word_list_selected: usize,
     word_list_visible_start: usize,
     color_scheme_selected: usize,
+    color_scheme_visible_start: usize,
     cursor_style_selected: usize,
+    word_option_selected: usize,
+    word_options: WordOptions,
     word_lists: Vec<String>,
     color_schemes: Vec<String>,
     cursor_styles: Vec<String>,
     filter: String,
+    download: Option<DownloadStatus>,
+    /// Frame of the download spinner, advanced on every update
+    spinner: usize,
 }
 
-impl Default for PopupManager {
-    fn default() -> Self {
+impl PopupManager {
+    /// Starts with the active color scheme and cursor style highlighted.
+    pub fn new(
+        color_schemes: Vec<String>,
+        color_scheme_selected: usize,
+        cursor_style_selected: usize,
+        word_options: WordOptions,
+    ) -> Self {
         let downloaded_langs = downloaded();
         let mut available = languages();
 
@@ -77,47 +106,60 @@ impl Default for PopupManager {
         word_lists.extend(downloaded_langs);
         word_lists.extend(available);
 
-        Self {
+        let mut popup = Self {
             is_open: false,
             current_section: Section::WordList,
             word_list_selected: 0,
             word_list_visible_start: 0,
-            color_scheme_selected: 0,
-            cursor_style_selected: 0,
+            color_scheme_selected,
+            color_scheme_visible_start: 0,
+            cursor_style_selected,
+            word_option_selected: 0,
+            word_options,
             word_lists,
-            color_schemes: vec![
-                "gruvbox".to_string(),
-                "dracula".to_string(),
-                "nord".to_string(),
-                "solarized".to_string(),
-            ],
+            color_schemes,
             cursor_styles: vec![
                 "underline".to_string(),
                 "block".to_string(),
                 "default".to_string(),
             ],
             filter: String::new(),
-        }
-    }
-}
-
-impl PopupManager {
-    pub fn new() -> Self {
-        Self::default()
+            download: None,
+            spinner: 0,
+        };
+        popup.update_color_scheme_scroll();
+        popup
     }
 
     pub fn toggle(&mut self) {
         self.is_open = !self.is_open;
+        self.clear_failed_download();
     }
 
     pub fn close(&mut self) {
         self.is_open = false;
+        self.clear_failed_download();
+    }
+
+    pub fn set_download(&mut self, download: Option<DownloadStatus>) {
+        self.spinner = (self.spinner + 1) % SPINNER.len();
+        self.download = download;
+    }
+
+    fn clear_failed_download(&mut self) {
+        if matches!(self.download, Some(DownloadStatus::Failed { .. })) {
+            self.download = None;
+        }
     }
 
     pub fn is_open(&self) -> bool {
         self.is_open
     }
 
+    pub fn set_word_options(&mut self, word_options: WordOptions) {
+        self.word_options = word_options;
+    }
+
     pub fn refresh_languages(&mut self) {
         let downloaded_langs = downloaded();
         let mut available = languages();
@@ -139,6 +181,9 @@ impl PopupManager {
         }
 
         match key {
+            KeyCode::Esc if matches!(self.download, Some(DownloadStatus::Running { .. })) => {
+                PopupAction::CancelDownload
+            }
             KeyCode::Esc => {
                 self.close();
                 PopupAction::Close
@@ -163,9 +208,25 @@ impl PopupManager {
             }
             KeyCode::Enter => {
                 let filtered = self.current_filtered();
+                if self.current_section == Section::WordOptions {
+                    // Toggles stay open so both can be flipped in one go
+                    match filtered.get(self.word_option_selected).map(String::as_str) {
+                        Some("punctuation") => {
+                            self.word_options.punctuation = !self.word_options.punctuation
+                        }
+                        Some("numbers") => self.word_options.numbers = !self.word_options.numbers,
+                        _ => return PopupAction::None,
+                    }
+                    return PopupAction::SetWordOptions(self.word_options);
+                }
                 let action = match self.current_section {
+                    // Stays open to show the download, if there is one
                     Section::WordList => {
-                        PopupAction::SelectWordList(filtered[self.word_list_selected].clone())
+                        return filtered
+                            .get(self.word_list_selected)
+                            .map_or(PopupAction::None, |name| {
+                                PopupAction::SelectWordList(name.clone())
+                            });
                     }
                     Section::ColorScheme => PopupAction::SelectColorScheme(
                         self.color_schemes
@@ -179,6 +240,7 @@ impl PopupManager {
                             .position(|x| x == &filtered[self.cursor_style_selected])
                             .unwrap_or(0),
                     ),
+                    Section::WordOptions => PopupAction::None,
                 };
                 self.close();
                 action
@@ -225,6 +287,7 @@ impl PopupManager {
             Section::ColorScheme => {
                 if self.color_scheme_selected > 0 {
                     self.color_scheme_selected -= 1;
+                    self.update_color_scheme_scroll();
                 }
             }
             Section::CursorStyle => {
@@ -232,6 +295,11 @@ impl PopupManager {
                     self.cursor_style_selected -= 1;
                 }
             }
+            Section::WordOptions => {
+                if self.word_option_selected > 0 {
+                    self.word_option_selected -= 1;
+                }
+            }
         }
     }
 
@@ -241,8 +309,12 @@ impl PopupManager {
                 self.word_list_selected = 0;
                 self.word_list_visible_start = 0;
             }
-            Section::ColorScheme => self.color_scheme_selected = 0,
+            Section::ColorScheme => {
+                self.color_scheme_selected = 0;
+                self.color_scheme_visible_start = 0;
+            }
             Section::CursorStyle => self.cursor_style_selected = 0,
+            Section::WordOptions => self.word_option_selected = 0,
         }
     }
 
@@ -250,21 +322,27 @@ impl PopupManager {
         let filtered_len = self.current_filtered().len();
         match self.current_section {
             Section::WordList => {
-                if self.word_list_selected < filtered_len - 1 {
+                if self.word_list_selected + 1 < filtered_len {
                     self.word_list_selected += 1;
                     self.update_word_list_scroll();
                 }
             }
             Section::ColorScheme => {
-                if self.color_scheme_selected < filtered_len - 1 {
+                if self.color_scheme_selected + 1 < filtered_len {
                     self.color_scheme_selected += 1;
+                    self.update_color_scheme_scroll();
                 }
             }
             Section::CursorStyle => {
-                if self.cursor_style_selected < filtered_len - 1 {
+                if self.cursor_style_selected + 1 < filtered_len {
                     self.cursor_style_selected += 1;
                 }
             }
+            Section::WordOptions => {
+                if self.word_option_selected + 1 < filtered_len {
+                    self.word_option_selected += 1;
+                }
+            }
         }
     }
 
@@ -273,6 +351,11 @@ impl PopupManager {
             Section::WordList => self.word_lists.filter(&self.filter),
             Section::ColorScheme => self.color_schemes.filter(&self.filter),
             Section::CursorStyle => self.cursor_styles.filter(&self.filter),
+            Section::WordOptions => WORD_OPTIONS
+                .iter()
+                .map(|option| option.to_string())
+                .collect::<Vec<_>>()
+                .filter(&self.filter),
         }
     }
 
@@ -285,12 +368,21 @@ impl PopupManager {
         }
     }
 
+    fn update_color_scheme_scroll(&mut self) {
+        const VISIBLE_COUNT: usize = 5;
+        if self.color_scheme_selected >= self.color_scheme_visible_start + VISIBLE_COUNT {
+            self.color_scheme_visible_start = self.color_scheme_selected - VISIBLE_COUNT + 1;
+        } else if self.color_scheme_selected < self.color_scheme_visible_start {
+            self.color_scheme_visible_start = self.color_scheme_selected;
+        }
+    }
+
     pub fn render<B: Backend>(&mut self, frame: &mut Frame<B>, area: Rect) {
         if !self.is_open {
             return;
         }
 
-        let popup_area = centered_rect(50, 12, area);
+        let popup_area = centered_rect(70, 12, area);
         frame.render_widget(Clear, popup_area);
 
         let block = Block::default()
@@ -305,15 +397,17 @@ impl PopupManager {
         let chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
             ])
             .split(popup_area.inner(&Margin::new(1, 1)));
 
         self.render_word_list(frame, chunks[0]);
         self.render_color_scheme_list(frame, chunks[1]);
         self.render_cursor_style_list(frame, chunks[2]);
+        self.render_word_options(frame, chunks[3]);
     }
 
     fn render_word_list<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
@@ -342,10 +436,22 @@ impl PopupManager {
                 };
 
                 let display_name = item.trim_end_matches(".json");
-                let text = if is_downloaded {
-                    format!("✓ {display_name}")
-                } else {
-                    display_name.to_string()
+                let text = match &self.download {
+                    Some(DownloadStatus::Running { name, progress }) if name == item => {
+                        format!(
+                            "{} {display_name} {}",
+                            SPINNER[self.spinner],
+                            format_progress(*progress)
+                        )
+                    }
+                    Some(DownloadStatus::Failed { name }) if name == item => {
+                        return ListItem::new(Line::from(Span::styled(
+                            format!("✗ {display_name}"),
+                            style.fg(Color::Red),
+                        )));
+                    }
+                    _ if is_downloaded => format!("✓ {display_name}"),
+                    _ => display_name.to_string(),
                 };
                 ListItem::new(Line::from(Span::styled(text, style)))
             })
@@ -387,11 +493,14 @@ impl PopupManager {
         } else {
             self.color_schemes.clone()
         };
-        let items: Vec<ListItem> = filtered
+        let visible_items = &filtered[self.color_scheme_visible_start
+            ..(self.color_scheme_visible_start + 5).min(filtered.len())];
+        let items: Vec<ListItem> = visible_items
             .iter()
             .enumerate()
             .map(|(i, scheme)| {
-                let style = if i == self.color_scheme_selected && is_selected {
+                let actual_index = self.color_scheme_visible_start + i;
+                let style = if actual_index == self.color_scheme_selected && is_selected {
                     Style::default().bg(Color::Blue).fg(Color::White)
                 } else {
                     Style::default()
@@ -401,9 +510,18 @@ impl PopupManager {
             .collect();
 
         let title = if is_selected && !self.filter.is_empty() {
-            format!("Color Schemes [{}]", self.filter)
+            format!(
+                "Color Schemes ({}/{}) [{}]",
+                self.color_scheme_selected + 1,
+                filtered.len(),
+                self.filter
+            )
         } else {
-            "Color Schemes".to_string()
+            format!(
+                "Color Schemes ({}/{})",
+                self.color_scheme_selected + 1,
+                filtered.len()
+            )
         };
         frame.render_widget(
             List::new(items).block(
@@ -459,6 +577,63 @@ impl PopupManager {
             area,
         );
     }
+
+    fn render_word_options<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let is_selected = matches!(self.current_section, Section::WordOptions);
+        let options: Vec<String> = WORD_OPTIONS.iter().map(|o| o.to_string()).collect();
+        let filtered = if is_selected {
+            options.filter(&self.filter)
+        } else {
+            options
+        };
+        let items: Vec<ListItem> = filtered
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                let enabled = match option.as_str() {
+                    "punctuation" => self.word_options.punctuation,
+                    _ => self.word_options.numbers,
+                };
+                let style = if i == self.word_option_selected && is_selected {
+                    Style::default().bg(Color::Blue).fg(Color::White)
+                } else if enabled {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default()
+                };
+                let mark = if enabled { "✓" } else { " " };
+                ListItem::new(Line::from(Span::styled(format!("{mark} {option}"), style)))
+            })
+            .collect();
+
+        let title = if is_selected && !self.filter.is_empty() {
+            format!("Options [{}]", self.filter)
+        } else {
+            "Options".to_string()
+        };
+        frame.render_widget(
+            List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(if is_selected {
+                        Style::default().fg(Color::Yellow)
+                    } else {
+                        Style::default().fg(Color::Gray)
+                    }),
+            ),
+            area,
+        );
+    }
+}
+
+/// Kilobytes downloaded, out of the total when it's known.
+fn format_progress(progress: Progress) -> String {
+    let kilobytes = |bytes: u64| (bytes + 1023) / 1024;
+    match progress.total {
+        Some(total) => format!("{}/{}K", kilobytes(progress.bytes), kilobytes(total)),
+        None => format!("{}K", kilobytes(progress.bytes)),
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -480,3 +655,26 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_rounds_up_to_whole_kilobytes() {
+        let progress = |bytes, total| Progress { bytes, total };
+        assert_eq!(format_progress(progress(0, None)), "0K");
+        assert_eq!(format_progress(progress(1, None)), "1K");
+        assert_eq!(format_progress(progress(2048, Some(10_000))), "2/10K");
+        assert_eq!(format_progress(progress(10_000, Some(10_000))), "10/10K");
+    }
+
+    #[test]
+    fn moving_down_with_nothing_matching_the_filter() {
+        let mut popup = PopupManager::new(vec!["nord".to_string()], 0, 0, WordOptions::default());
+        popup.current_section = Section::WordOptions;
+        popup.filter = "z".to_string();
+        popup.move_down();
+        assert_eq!(popup.word_option_selected, 0);
+    }
+}
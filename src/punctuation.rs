@@ -0,0 +1,133 @@
+use rand::Rng;
+
+/// Chance that a word is swapped for a number.
+const NUMBER_CHANCE: f64 = 0.1;
+
+/// Chance that a dash is put between two words.
+const DASH_CHANCE: f64 = 0.01;
+
+/// Extras mixed into generated words, like monkeytype's punctuation and
+/// numbers modes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WordOptions {
+    pub punctuation: bool,
+    pub numbers: bool,
+}
+
+/// Turns plain words into something closer to real text. Numbers replace
+/// words at random; punctuation capitalizes sentence starts and sprinkles in
+/// periods, commas, quotes and the like at roughly monkeytype's frequencies.
+pub fn apply(words: Vec<String>, options: WordOptions) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let count = words.len();
+    let mut result: Vec<String> = Vec::with_capacity(count);
+
+    for (i, word) in words.into_iter().enumerate() {
+        let mut word = if options.numbers && rng.gen_bool(NUMBER_CHANCE) {
+            number(&mut rng)
+        } else {
+            word
+        };
+        if options.punctuation {
+            let previous = result.last().map(String::as_str);
+            let last = i + 1 == count;
+            if dash(previous, last, &mut rng) {
+                // A dash stands on its own between two words
+                result.push("-".to_string());
+            } else {
+                word = punctuate(word, previous, last, &mut rng);
+            }
+        }
+        result.push(word);
+    }
+
+    result
+}
+
+fn punctuate(word: String, previous: Option<&str>, last: bool, rng: &mut impl Rng) -> String {
+    let previous_end = previous.and_then(|word| word.chars().last());
+    let sentence_start = previous_end.map_or(true, |ch| matches!(ch, '.' | '?' | '!'));
+    let after_comma = previous_end == Some(',');
+
+    if sentence_start {
+        let word = capitalize(&word);
+        return if last {
+            format!("{word}{}", sentence_end(rng))
+        } else {
+            word
+        };
+    }
+
+    let roll: f64 = rng.gen();
+    if last || (roll < 0.1 && !after_comma) {
+        format!("{word}{}", sentence_end(rng))
+    } else if roll < 0.11 {
+        format!("\"{word}\"")
+    } else if roll < 0.12 {
+        format!("'{word}'")
+    } else if roll < 0.13 {
+        format!("({word})")
+    } else if roll < 0.14 {
+        format!("{word}:")
+    } else if roll < 0.15 {
+        format!("{word};")
+    } else if roll < 0.36 && !after_comma {
+        format!("{word},")
+    } else {
+        word
+    }
+}
+
+/// Whether a dash goes in front of the next word. Never at the start or end
+/// of a sentence, or right after other punctuation.
+fn dash(previous: Option<&str>, last: bool, rng: &mut impl Rng) -> bool {
+    let previous_plain = previous
+        .and_then(|word| word.chars().last())
+        .is_some_and(char::is_alphanumeric);
+    previous_plain && !last && rng.gen_bool(DASH_CHANCE)
+}
+
+fn sentence_end(rng: &mut impl Rng) -> char {
+    match rng.gen_range(0..10) {
+        0 => '?',
+        1 => '!',
+        _ => '.',
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// A number of one to four digits without a leading zero.
+fn number(rng: &mut impl Rng) -> String {
+    let digits = rng.gen_range(1..=4);
+    let mut number = rng.gen_range(1..=9).to_string();
+    for _ in 1..digits {
+        number.push(char::from(b'0' + rng.gen_range(0..=9)));
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dashes_are_added_between_words_not_swapped_in() {
+        let words: Vec<String> = (0..2000).map(|i| format!("word{i}")).collect();
+        let options = WordOptions {
+            punctuation: true,
+            numbers: false,
+        };
+        let result = apply(words, options);
+        let kept = result.iter().filter(|word| word.as_str() != "-").count();
+        assert_eq!(kept, 2000);
+        assert!(result.first().is_some_and(|word| word != "-"));
+        assert!(result.last().is_some_and(|word| word != "-"));
+    }
+}
@@ -0,0 +1,195 @@
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::words::cache_dir;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Quote {
+    pub text: String,
+    pub source: String,
+    pub length: usize,
+    pub id: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct QuoteCollection {
+    #[serde(default)]
+    groups: Vec<[usize; 2]>,
+    quotes: Vec<Quote>,
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteLength {
+    Short,
+    Medium,
+    Long,
+    Thicc,
+}
+
+impl QuoteLength {
+    fn group(self) -> usize {
+        match self {
+            QuoteLength::Short => 0,
+            QuoteLength::Medium => 1,
+            QuoteLength::Long => 2,
+            QuoteLength::Thicc => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for QuoteLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{self:?}").to_lowercase();
+        write!(f, "{name}")
+    }
+}
+
+/// Quotes of a single language, narrowed down to the requested length group.
+#[derive(Debug, Clone)]
+pub struct QuotePool {
+    quotes: Vec<Quote>,
+    language: String,
+    length: Option<QuoteLength>,
+}
+
+impl QuotePool {
+    /// Picks out the length group, or the whole collection when the group is
+    /// empty. Fails only if the collection has no quotes at all.
+    fn new(
+        language: &str,
+        collection: QuoteCollection,
+        length: Option<QuoteLength>,
+    ) -> Result<Self, String> {
+        let group = match length.and_then(|length| collection.groups.get(length.group())) {
+            Some(&[min, max]) => collection
+                .quotes
+                .iter()
+                .filter(|quote| (min..=max).contains(&quote.length))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let (quotes, length) = if group.is_empty() {
+            (collection.quotes, None)
+        } else {
+            (group, length)
+        };
+        if quotes.is_empty() {
+            return Err(format!("there are no {language} quotes"));
+        }
+        Ok(Self {
+            quotes,
+            language: language.to_string(),
+            length,
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn length(&self) -> Option<QuoteLength> {
+        self.length
+    }
+
+    pub fn choose(&self) -> Quote {
+        let mut quote = self
+            .quotes
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .expect("quote pool is never empty");
+        // Quotes occasionally contain line breaks or doubled spaces
+        quote.text = quote.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        quote
+    }
+}
+
+/// Accepts a collection name, rejecting anything that could reach outside
+/// the quotes directory or URL.
+pub fn parse_language(language: &str) -> Result<String, String> {
+    if language.is_empty() || language.contains(['/', '\\']) {
+        return Err(format!("`{language}` isn't a language name"));
+    }
+    Ok(language.to_string())
+}
+
+fn quotes_dir() -> PathBuf {
+    cache_dir().join("quotes")
+}
+
+/// Loads a quote collection from the cache, downloading it the first time.
+pub async fn download(
+    language: &str,
+    length: Option<QuoteLength>,
+) -> Result<QuotePool, Box<dyn std::error::Error + Send + Sync>> {
+    let language = parse_language(language)?;
+    let url = format!("https://raw.githubusercontent.com/monkeytypegame/monkeytype/refs/heads/master/frontend/static/quotes/{language}.json");
+
+    let dir = quotes_dir();
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{language}.json"));
+
+    // Return cached file if exists
+    if path.exists() {
+        let content = fs::read_to_string(&path)?;
+        let collection: QuoteCollection = serde_json::from_str(&content)?;
+        return Ok(QuotePool::new(&language, collection, length)?);
+    }
+
+    // Download, cache, and return
+    let content = reqwest::get(&url).await?.error_for_status()?.text().await?;
+    let collection: QuoteCollection = serde_json::from_str(&content)?;
+    fs::write(&path, &content)?;
+    Ok(QuotePool::new(&language, collection, length)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(lengths: &[usize]) -> QuoteCollection {
+        QuoteCollection {
+            groups: vec![[0, 100], [101, 300], [301, 600], [601, 9999]],
+            quotes: lengths
+                .iter()
+                .enumerate()
+                .map(|(id, &length)| Quote {
+                    text: "x".repeat(length),
+                    source: String::new(),
+                    length,
+                    id,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn picks_the_length_group() {
+        let pool = QuotePool::new(
+            "english",
+            collection(&[50, 200, 700]),
+            Some(QuoteLength::Medium),
+        );
+        let pool = pool.unwrap();
+        assert_eq!(pool.length(), Some(QuoteLength::Medium));
+        assert_eq!(pool.choose().length, 200);
+    }
+
+    #[test]
+    fn empty_group_falls_back_to_the_whole_collection() {
+        let pool = QuotePool::new("english", collection(&[50, 200]), Some(QuoteLength::Thicc));
+        let pool = pool.unwrap();
+        assert_eq!(pool.length(), None);
+        assert_eq!(pool.quotes.len(), 2);
+    }
+
+    #[test]
+    fn empty_collection_is_an_error() {
+        assert!(QuotePool::new("klingon", collection(&[]), None).is_err());
+    }
+}
@@ -0,0 +1,498 @@
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::app::App;
+use crate::config::Config;
+use crate::ui;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Id the host's own racer has on the server.
+const HOST_ID: usize = 0;
+
+/// One racer as shown in everyone's standings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Racer {
+    pub name: String,
+    /// Share of the text typed, from 0 to 1
+    pub progress: f64,
+    pub wpm: f64,
+    /// Finishing place, once finished
+    pub place: Option<usize>,
+}
+
+/// Lines of JSON exchanged between the host and its racers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    /// A racer introduces itself
+    Join { name: String },
+    /// The host starts the race with the text everyone types
+    Start { text: String },
+    /// A racer's position in the text
+    Progress {
+        position: usize,
+        wpm: f64,
+        finished: bool,
+    },
+    /// Everyone's standings, sent by the host whenever they change
+    Racers { racers: Vec<Racer> },
+}
+
+/// What the server hears from the connections it manages.
+enum ServerEvent {
+    Connected(usize, UnboundedSender<Message>),
+    Received(usize, Message),
+    Disconnected(usize),
+}
+
+/// The local end of a race: messages to send and messages received.
+struct Connection {
+    outgoing: UnboundedSender<Message>,
+    incoming: UnboundedReceiver<Message>,
+}
+
+pub fn default_name() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "racer".to_string())
+}
+
+/// Starts a race server on `bind:port` and joins it through in-process
+/// channels.
+async fn host(
+    bind: IpAddr,
+    port: u16,
+    name: String,
+) -> Result<Connection, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind((bind, port))
+        .await
+        .map_err(|e| format!("failed to listen on {bind} port {port}: {e}"))?;
+    Ok(serve_on(listener, name))
+}
+
+/// Runs the race server on a bound listener, with the host as its first
+/// racer.
+fn serve_on(listener: TcpListener, name: String) -> Connection {
+    let (events, server_events) = mpsc::unbounded_channel();
+
+    // The host is a racer like any other, just without a socket
+    let (outgoing, local_outgoing) = mpsc::unbounded_channel();
+    let (local_incoming, incoming) = mpsc::unbounded_channel();
+    let _ = events.send(ServerEvent::Connected(HOST_ID, local_incoming));
+    forward(HOST_ID, local_outgoing, events.clone());
+    let _ = outgoing.send(Message::Join { name });
+
+    tokio::spawn(accept(listener, events));
+    tokio::spawn(serve(server_events));
+    Connection { outgoing, incoming }
+}
+
+/// Passes the local racer's messages on to the server.
+fn forward(
+    id: usize,
+    mut messages: UnboundedReceiver<Message>,
+    events: UnboundedSender<ServerEvent>,
+) {
+    tokio::spawn(async move {
+        while let Some(message) = messages.recv().await {
+            if events.send(ServerEvent::Received(id, message)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn accept(listener: TcpListener, events: UnboundedSender<ServerEvent>) {
+    let mut next_id = HOST_ID + 1;
+    while let Ok((stream, _)) = listener.accept().await {
+        let id = next_id;
+        next_id += 1;
+
+        let (reader, writer) = stream.into_split();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        if events.send(ServerEvent::Connected(id, sender)).is_err() {
+            break;
+        }
+        tokio::spawn(write_lines(writer, receiver));
+
+        let events = events.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                // Ignore anything that isn't one of our messages
+                if let Ok(message) = serde_json::from_str(&line) {
+                    let _ = events.send(ServerEvent::Received(id, message));
+                }
+            }
+            let _ = events.send(ServerEvent::Disconnected(id));
+        });
+    }
+}
+
+/// Keeps the standings and relays the race to every racer.
+async fn serve(mut events: UnboundedReceiver<ServerEvent>) {
+    let mut clients: BTreeMap<usize, UnboundedSender<Message>> = BTreeMap::new();
+    let mut racers: BTreeMap<usize, Racer> = BTreeMap::new();
+    let mut text: Option<(String, usize)> = None;
+
+    while let Some(event) = events.recv().await {
+        match event {
+            ServerEvent::Connected(id, sender) => {
+                clients.insert(id, sender);
+            }
+            ServerEvent::Disconnected(id) => {
+                clients.remove(&id);
+                racers.remove(&id);
+            }
+            ServerEvent::Received(id, Message::Join { name }) => {
+                racers.insert(
+                    id,
+                    Racer {
+                        name,
+                        progress: 0.0,
+                        wpm: 0.0,
+                        place: None,
+                    },
+                );
+                // Late arrivals still get the text
+                if let (Some((text, _)), Some(client)) = (&text, clients.get(&id)) {
+                    let _ = client.send(Message::Start { text: text.clone() });
+                }
+            }
+            ServerEvent::Received(HOST_ID, Message::Start { text: started }) => {
+                let length = started.graphemes(true).count().max(1);
+                for client in clients.values() {
+                    let _ = client.send(Message::Start {
+                        text: started.clone(),
+                    });
+                }
+                text = Some((started, length));
+            }
+            ServerEvent::Received(
+                id,
+                Message::Progress {
+                    position,
+                    wpm,
+                    finished,
+                },
+            ) => {
+                let finishers = racers.values().filter(|r| r.place.is_some()).count();
+                if let (Some(racer), Some((_, length))) = (racers.get_mut(&id), &text) {
+                    racer.progress = (position as f64 / *length as f64).min(1.0);
+                    racer.wpm = wpm;
+                    if finished && racer.place.is_none() {
+                        racer.place = Some(finishers + 1);
+                    }
+                }
+            }
+            // Only the host starts races, and only the host sends standings
+            ServerEvent::Received(_, Message::Start { .. } | Message::Racers { .. }) => continue,
+        }
+
+        let standings: Vec<Racer> = racers.values().cloned().collect();
+        for client in clients.values() {
+            let _ = client.send(Message::Racers {
+                racers: standings.clone(),
+            });
+        }
+    }
+}
+
+async fn write_lines(
+    mut writer: impl AsyncWriteExt + Unpin,
+    mut messages: UnboundedReceiver<Message>,
+) {
+    while let Some(message) = messages.recv().await {
+        let Ok(mut line) = serde_json::to_string(&message) else {
+            continue;
+        };
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Connects to a host, adding the default port if `addr` has none.
+/// Adds the default port to an address without one. Bare IPv6 addresses are
+/// full of colons, so they're recognized before looking for a port.
+fn with_default_port(addr: &str) -> String {
+    let unbracketed = addr.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        SocketAddr::new(ip, DEFAULT_PORT).to_string()
+    } else if addr.contains(':') {
+        addr.to_string()
+    } else {
+        format!("{addr}:{DEFAULT_PORT}")
+    }
+}
+
+async fn join(addr: &str, name: String) -> Result<Connection, Box<dyn std::error::Error>> {
+    let addr = with_default_port(addr);
+    let stream = TcpStream::connect(&addr)
+        .await
+        .map_err(|e| format!("failed to connect to {addr}: {e}"))?;
+    let (reader, writer) = stream.into_split();
+
+    let (outgoing, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(write_lines(writer, receiver));
+    let (sender, incoming) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Ok(message) = serde_json::from_str(&line) {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let _ = outgoing.send(Message::Join { name });
+    Ok(Connection { outgoing, incoming })
+}
+
+/// Where a race comes from, as picked on the command line.
+pub enum Role {
+    /// Host on an address and port, racing on the given text
+    Host {
+        bind: IpAddr,
+        port: u16,
+        text: String,
+    },
+    Join {
+        addr: String,
+    },
+}
+
+/// A race in progress, from one racer's point of view.
+pub struct Race {
+    connection: Connection,
+    app: App,
+    config: Config,
+    is_host: bool,
+    started: bool,
+    racers: Vec<Racer>,
+    /// Last position, finish and whole wpm sent, to only send changes
+    sent: Option<(usize, bool, u32)>,
+}
+
+/// Sets up the connection. Done before the terminal is taken over so
+/// network errors print normally.
+pub async fn connect(
+    role: Role,
+    name: String,
+    config: Config,
+) -> Result<Race, Box<dyn std::error::Error>> {
+    let (connection, text, is_host) = match role {
+        Role::Host { bind, port, text } => (host(bind, port, name).await?, text, true),
+        // Racers get their text when the race starts
+        Role::Join { addr } => (join(&addr, name).await?, String::new(), false),
+    };
+
+    Ok(Race {
+        connection,
+        app: App::with_text(&text, config.clone()),
+        config,
+        is_host,
+        started: false,
+        racers: Vec::new(),
+        sent: None,
+    })
+}
+
+impl Race {
+    pub async fn run(
+        mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            while let Ok(message) = self.connection.incoming.try_recv() {
+                match message {
+                    Message::Start { text } => {
+                        if !self.is_host {
+                            self.app = App::with_text(&text, self.config.clone());
+                        }
+                        self.started = true;
+                    }
+                    Message::Racers { racers } => self.racers = racers,
+                    Message::Join { .. } | Message::Progress { .. } => {}
+                }
+            }
+
+            self.app.tick();
+            self.app.save_finished();
+            if self.started {
+                self.send_progress();
+            }
+            self.draw(terminal)?;
+
+            if event::poll(Duration::from_millis(50))? {
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        KeyCode::Esc => break,
+                        KeyCode::Enter if self.is_host && !self.started => {
+                            let _ = self.connection.outgoing.send(Message::Start {
+                                text: self.app.target().concat(),
+                            });
+                        }
+                        _ if self.started && !self.app.is_done() => {
+                            self.app.handle_typing_key(key.code, key.modifiers)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send_progress(&mut self) {
+        let wpm = self.app.net_wpm();
+        let progress = (
+            self.app.input().len(),
+            self.app.is_done(),
+            wpm.round() as u32,
+        );
+        if self.sent == Some(progress) {
+            return;
+        }
+        self.sent = Some(progress);
+        let _ = self.connection.outgoing.send(Message::Progress {
+            position: progress.0,
+            wpm,
+            finished: progress.1,
+        });
+    }
+
+    fn draw(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let app = &self.app;
+        terminal.draw(|frame| {
+            if !self.started {
+                let help = if self.is_host {
+                    "Enter start | Esc quit"
+                } else {
+                    "Waiting for the host to start | Esc quit"
+                };
+                ui::render_standings(frame, &self.racers, app.theme(), "race lobby", help);
+            } else if app.is_done() {
+                let title = format!(
+                    "race results | wpm: {:.0} | acc: {:.0}%",
+                    app.net_wpm(),
+                    app.accuracy()
+                );
+                ui::render_standings(frame, &self.racers, app.theme(), &title, "Esc quit");
+            } else {
+                ui::render_typing_test(
+                    frame,
+                    ui::RenderConfig {
+                        target: app.target(),
+                        input: app.input(),
+                        net_wpm: app.net_wpm(),
+                        raw_wpm: app.raw_wpm(),
+                        accuracy: app.accuracy(),
+                        consistency: app.consistency(),
+                        theme: app.theme(),
+                        cursor_style_index: app.cursor_style_index,
+                        right_to_left: app.is_right_to_left(),
+                        ghost: None,
+                        racers: &self.racers,
+                        help: "Esc quit",
+                    },
+                );
+            }
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Waits for the next message the predicate picks out, skipping others.
+    async fn expect(
+        connection: &mut Connection,
+        mut wanted: impl FnMut(&Message) -> bool,
+    ) -> Message {
+        let wait = async {
+            loop {
+                let message = connection.incoming.recv().await.expect("connection closed");
+                if wanted(&message) {
+                    return message;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), wait)
+            .await
+            .expect("timed out waiting for a message")
+    }
+
+    fn standings(message: Message) -> Vec<Racer> {
+        match message {
+            Message::Racers { racers } => racers,
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn adds_the_default_port() {
+        assert_eq!(with_default_port("localhost"), "localhost:7878");
+        assert_eq!(with_default_port("localhost:9000"), "localhost:9000");
+        assert_eq!(with_default_port("192.168.1.20"), "192.168.1.20:7878");
+        assert_eq!(with_default_port("::1"), "[::1]:7878");
+        assert_eq!(with_default_port("[fe80::1]"), "[fe80::1]:7878");
+        assert_eq!(with_default_port("[::1]:9000"), "[::1]:9000");
+    }
+
+    #[tokio::test]
+    async fn race_over_localhost() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let mut host = serve_on(listener, "host".to_string());
+        let mut guest = join(&addr, "guest".to_string()).await.unwrap();
+
+        let both_joined =
+            |message: &Message| matches!(message, Message::Racers { racers } if racers.len() == 2);
+        expect(&mut host, both_joined).await;
+
+        let text = "ab cd".to_string();
+        host.outgoing
+            .send(Message::Start { text: text.clone() })
+            .unwrap();
+        let started = expect(&mut guest, |message| {
+            matches!(message, Message::Start { .. })
+        })
+        .await;
+        assert!(matches!(started, Message::Start { text: started } if started == text));
+
+        let finish = Message::Progress {
+            position: 5,
+            wpm: 60.0,
+            finished: true,
+        };
+        guest.outgoing.send(finish.clone()).unwrap();
+        let guest_done = |message: &Message| matches!(message, Message::Racers { racers } if racers.iter().any(|r| r.place.is_some()));
+        expect(&mut host, guest_done).await;
+        host.outgoing.send(finish).unwrap();
+
+        let all_done = |message: &Message| matches!(message, Message::Racers { racers } if racers.iter().all(|r| r.place.is_some()));
+        let racers = standings(expect(&mut guest, all_done).await);
+        let place = |name: &str| racers.iter().find(|r| r.name == name).and_then(|r| r.place);
+        assert_eq!(place("guest"), Some(1));
+        assert_eq!(place("host"), Some(2));
+        assert!(racers.iter().all(|r| r.progress == 1.0));
+    }
+}
@@ -0,0 +1,195 @@
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::app::App;
+use crate::history::{data_dir, Record};
+use crate::keylog::KeyEvent;
+use crate::ui;
+
+/// How far the arrow keys jump.
+const SEEK_STEP: Duration = Duration::from_secs(2);
+
+/// A finished test with its keystrokes, stored as `replays/<id>.json`. The id
+/// is the test's timestamp, with a `-2`, `-3`... suffix for tests finished in
+/// the same second.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Replay {
+    #[serde(skip)]
+    pub id: String,
+    #[serde(flatten)]
+    pub record: Record,
+    pub events: Vec<KeyEvent>,
+}
+
+fn replays_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+fn replay_path(id: &str) -> PathBuf {
+    replays_dir().join(format!("{id}.json"))
+}
+
+pub fn save(record: &Record, events: &[KeyEvent]) -> Result<(), Box<dyn std::error::Error>> {
+    let replay = Replay {
+        id: String::new(),
+        record: record.clone(),
+        events: events.to_vec(),
+    };
+    fs::create_dir_all(replays_dir())?;
+    let path = (1..)
+        .map(|n| match n {
+            1 => record.timestamp.to_string(),
+            n => format!("{}-{n}", record.timestamp),
+        })
+        .map(|id| replay_path(&id))
+        .find(|path| !path.exists())
+        .expect("some id should be free");
+    fs::write(path, serde_json::to_string(&replay)?)?;
+    Ok(())
+}
+
+/// Stored replays, oldest first.
+pub fn list() -> Vec<Replay> {
+    let mut replays: Vec<Replay> = fs::read_dir(replays_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| read(&entry.path()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    replays.sort_by_key(|replay| (replay.record.timestamp, replay.id.len(), replay.id.clone()));
+    replays
+}
+
+fn read(path: &Path) -> Result<Replay, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let replay: Replay =
+        serde_json::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))?;
+    let id = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    Ok(Replay { id, ..replay })
+}
+
+/// Finds a replay by id or file path, or the latest one.
+pub fn load(id_or_path: Option<&str>) -> Result<Replay, Box<dyn std::error::Error>> {
+    let Some(id_or_path) = id_or_path else {
+        return list().pop().ok_or_else(|| "no replays recorded yet".into());
+    };
+
+    let path = Path::new(id_or_path);
+    if path.is_file() {
+        return read(path);
+    }
+    let is_id = !id_or_path.contains(['/', '\\']);
+    if is_id && replay_path(id_or_path).is_file() {
+        return read(&replay_path(id_or_path));
+    }
+    Err(format!("no replay with id or path `{id_or_path}`").into())
+}
+
+pub fn print_list() {
+    let replays = list();
+    if replays.is_empty() {
+        println!("No replays recorded yet.");
+        return;
+    }
+
+    println!("{:<14}{:<16}{:>6}{:>9}", "id", "mode", "wpm", "final");
+    for replay in replays {
+        let record = &replay.record;
+        println!(
+            "{:<14}{:<16}{:>6.1}{:>8.1}%",
+            replay.id,
+            record.mode.to_string(),
+            record.wpm,
+            record.final_accuracy
+        );
+    }
+}
+
+/// Plays back a replay through the typing screen. Space pauses, the arrow
+/// keys seek, 1, 2 and 4 set the speed and Esc quits.
+pub fn play(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mut app: App,
+    replay: &Replay,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let end = replay
+        .events
+        .last()
+        .map_or(Duration::ZERO, |event| event.elapsed());
+    let mut position = Duration::ZERO;
+    let mut speed = 1;
+    let mut paused = false;
+    let mut last_frame = Instant::now();
+
+    loop {
+        if !paused {
+            position = (position + last_frame.elapsed() * speed).min(end);
+        }
+        last_frame = Instant::now();
+        app.replay(&replay.events, position);
+
+        let state = if position == end {
+            "done"
+        } else if paused {
+            "paused"
+        } else {
+            "playing"
+        };
+        let help = format!(
+            "{state} {speed}x {:.1}s / {:.1}s | Space pause | ←/→ seek | 1/2/4 speed | Esc quit",
+            position.as_secs_f64(),
+            end.as_secs_f64()
+        );
+        terminal.draw(|frame| {
+            ui::render_typing_test(
+                frame,
+                ui::RenderConfig {
+                    target: app.target(),
+                    input: app.input(),
+                    net_wpm: app.net_wpm(),
+                    raw_wpm: app.raw_wpm(),
+                    accuracy: app.accuracy(),
+                    consistency: app.consistency(),
+                    theme: app.theme(),
+                    cursor_style_index: app.cursor_style_index,
+                    right_to_left: app.is_right_to_left(),
+                    ghost: None,
+                    racers: &[],
+                    help: &help,
+                },
+            );
+        })?;
+
+        if event::poll(Duration::from_millis(16))? {
+            if let Event::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => break,
+                    KeyCode::Char(' ') if position == end => {
+                        position = Duration::ZERO;
+                        paused = false;
+                    }
+                    KeyCode::Char(' ') => paused = !paused,
+                    KeyCode::Left => position = position.saturating_sub(SEEK_STEP),
+                    KeyCode::Right => position = (position + SEEK_STEP).min(end),
+                    KeyCode::Home => position = Duration::ZERO,
+                    KeyCode::End => position = end,
+                    KeyCode::Char('1') => speed = 1,
+                    KeyCode::Char('2') => speed = 2,
+                    KeyCode::Char('4') => speed = 4,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use crate::history::{self, Record};
+use crate::keys::KeyStats;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Restricts which history records are summarized.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub word_list: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl Filter {
+    fn matches(&self, record: &Record) -> bool {
+        let word_list = self
+            .word_list
+            .as_deref()
+            .map(|name| name.trim_end_matches(".json"));
+
+        word_list.map_or(true, |name| record.word_list == name)
+            && self.since.map_or(true, |since| record.timestamp >= since)
+            && self
+                .until
+                .map_or(true, |until| record.timestamp < until + SECONDS_PER_DAY)
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date into the unix timestamp of its midnight (UTC).
+pub fn parse_date(date: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid date `{date}`, expected YYYY-MM-DD");
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: i64 = month.parse().map_err(|_| invalid())?;
+    let day: i64 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || year < 1970 {
+        return Err(invalid());
+    }
+    let is_leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Days since the epoch for a proleptic Gregorian date (Howard Hinnant's algorithm)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Ok(days as u64 * SECONDS_PER_DAY)
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else {
+        format!("{minutes}m {seconds:02}s")
+    }
+}
+
+fn format_percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| format!("{value:.1}%"))
+}
+
+fn average_row(label: &str, records: &[&Record]) -> String {
+    let count = records.len() as f64;
+    let average = |value: fn(&Record) -> f64| records.iter().map(|r| value(r)).sum::<f64>() / count;
+    // Older records only know their final accuracy, so keystroke accuracy
+    // averages over the ones that have it
+    let keystroke: Vec<f64> = records
+        .iter()
+        .filter_map(|r| r.keystroke_accuracy)
+        .collect();
+    let keystroke_average =
+        (!keystroke.is_empty()).then(|| keystroke.iter().sum::<f64>() / keystroke.len() as f64);
+    format!(
+        "{label:<16}{:>6.1}{:>8.1}{:>9}{:>9}",
+        average(|r| r.wpm),
+        average(|r| r.raw_wpm),
+        format_percent(keystroke_average),
+        format_percent(Some(average(|r| r.final_accuracy)))
+    )
+}
+
+/// Prints a plain-text summary of the history to stdout.
+pub fn print(filter: &Filter) {
+    let records: Vec<Record> = history::load()
+        .into_iter()
+        .filter(|record| filter.matches(record))
+        .collect();
+
+    if records.is_empty() {
+        println!("No tests recorded yet.");
+        return;
+    }
+
+    let all: Vec<&Record> = records.iter().collect();
+    let total_time: f64 = records.iter().map(|r| r.duration).sum();
+
+    println!("{:<16}{}", "tests", records.len());
+    println!("{:<16}{}", "time typed", format_duration(total_time));
+    println!();
+    println!(
+        "{:<16}{:>6}{:>8}{:>9}{:>9}",
+        "", "wpm", "raw", "acc", "final"
+    );
+    println!("{}", average_row("average", &all));
+    // Windows longer than the history average over all of it
+    for window in [10, 100] {
+        let recent = &all[all.len().saturating_sub(window)..];
+        println!("{}", average_row(&format!("last {window}"), recent));
+    }
+
+    // Personal bests, grouped by mode in order of first appearance
+    let mut bests: Vec<&Record> = Vec::new();
+    for record in &records {
+        match bests.iter_mut().find(|best| best.mode == record.mode) {
+            Some(best) if record.wpm > best.wpm => *best = record,
+            Some(_) => {}
+            None => bests.push(record),
+        }
+    }
+
+    println!();
+    println!("personal bests");
+    for best in bests {
+        println!(
+            "  {:<14}{:>6.1} wpm {:>7} acc {:>7} final  ({})",
+            best.mode.to_string(),
+            best.wpm,
+            format_percent(best.keystroke_accuracy),
+            format_percent(Some(best.final_accuracy)),
+            best.word_list
+        );
+    }
+
+    // Key stats cover every test, whatever the filter
+    let keys = KeyStats::load();
+    if !keys.is_empty() {
+        println!();
+        println!("weakest keys");
+        for (key, _) in keys.weakest().into_iter().take(5) {
+            let stat = keys.chars[&key];
+            println!(
+                "  {:<14}{:>5.1}% miss {:>6.0} ms",
+                key,
+                stat.error_rate() * 100.0,
+                stat.average_ms().unwrap_or(0.0)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Mode;
+
+    fn record(timestamp: u64, word_list: &str) -> Record {
+        Record {
+            timestamp,
+            mode: Mode::Words { count: 10 },
+            word_list: word_list.to_string(),
+            wpm: 60.0,
+            raw_wpm: 65.0,
+            keystroke_accuracy: Some(93.0),
+            final_accuracy: 95.0,
+            duration: 10.0,
+            target: String::new(),
+        }
+    }
+
+    #[test]
+    fn parses_dates_to_midnight_utc() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date("2024-02-29"), Ok(1_709_164_800));
+        assert_eq!(parse_date("2000-03-01"), Ok(951_868_800));
+    }
+
+    #[test]
+    fn rejects_impossible_dates() {
+        for date in [
+            "2024-02-31",
+            "2023-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+        ] {
+            assert!(parse_date(date).is_err(), "{date}");
+        }
+        for date in ["2024-1", "yesterday", "1969-12-31", ""] {
+            assert!(parse_date(date).is_err(), "{date}");
+        }
+    }
+
+    #[test]
+    fn filters_by_word_list_with_or_without_extension() {
+        let filter = Filter {
+            word_list: Some("english_1k.json".to_string()),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&record(0, "english_1k")));
+        assert!(!filter.matches(&record(0, "english")));
+    }
+
+    #[test]
+    fn until_includes_the_whole_day() {
+        let day = parse_date("2024-05-10").unwrap();
+        let filter = Filter {
+            since: Some(day),
+            until: Some(day),
+            ..Filter::default()
+        };
+        assert!(!filter.matches(&record(day - 1, "english")));
+        assert!(filter.matches(&record(day, "english")));
+        assert!(filter.matches(&record(day + SECONDS_PER_DAY - 1, "english")));
+        assert!(!filter.matches(&record(day + SECONDS_PER_DAY, "english")));
+    }
+}
@@ -0,0 +1,276 @@
+mod monkeytype;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::config_dir;
+use crate::ui::{ColorScheme, ThemeColors};
+use crate::words::cache_dir;
+
+pub use monkeytype::import;
+
+/// A color scheme with concrete colors, either built in or loaded from a theme file.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    text: Color,
+    done: Color,
+    skipped: Color,
+    error: Color,
+    accent: Color,
+    background: Option<Color>,
+}
+
+impl ThemeColors for &Theme {
+    fn text(self) -> Color {
+        self.text
+    }
+
+    fn done(self) -> Color {
+        self.done
+    }
+
+    fn skipped(self) -> Color {
+        self.skipped
+    }
+
+    fn error(self) -> Color {
+        self.error
+    }
+
+    fn accent(self) -> Color {
+        self.accent
+    }
+}
+
+impl Theme {
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+}
+
+impl From<ColorScheme> for Theme {
+    fn from(scheme: ColorScheme) -> Self {
+        Self {
+            name: scheme.to_string(),
+            text: scheme.text(),
+            done: scheme.done(),
+            skipped: scheme.skipped(),
+            error: scheme.error(),
+            accent: scheme.accent(),
+            background: None,
+        }
+    }
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Contents of a theme file, with colors written as `#rrggbb` or `#rgb`.
+#[derive(Serialize, Deserialize, Debug)]
+struct ThemeFile {
+    name: Option<String>,
+    text: String,
+    done: String,
+    skipped: String,
+    error: String,
+    accent: String,
+    background: Option<String>,
+}
+
+/// How many colors the terminal can show, guessed from the environment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorSupport {
+    fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorSupport::TrueColor
+        } else if term.contains("256") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    fn color(self, (r, g, b): (u8, u8, u8)) -> Color {
+        match self {
+            ColorSupport::TrueColor => Color::Rgb(r, g, b),
+            ColorSupport::Ansi256 => Color::Indexed(nearest_256((r, g, b))),
+            ColorSupport::Ansi16 => nearest_16((r, g, b)),
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<(u8, u8, u8), String> {
+    let digits = hex.trim().trim_start_matches('#');
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid color `{hex}`"));
+    }
+    // Alpha channels (#rgba, #rrggbbaa) are ignored
+    let expanded: String = match digits.len() {
+        3 | 4 => digits.chars().take(3).flat_map(|c| [c, c]).collect(),
+        6 | 8 => digits.chars().take(6).collect(),
+        _ => return Err(format!("invalid color `{hex}`")),
+    };
+    let channel = |i: usize| {
+        u8::from_str_radix(&expanded[i..i + 2], 16).map_err(|_| format!("invalid color `{hex}`"))
+    };
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Closest entry of the xterm 6x6x6 color cube or grayscale ramp.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|&i| (LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (16 + 36 * r + 6 * g + b) as u8;
+    let cube_rgb = (LEVELS[r], LEVELS[g], LEVELS[b]);
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = (average.saturating_sub(8) / 10).min(23) as u8;
+    let gray = 8 + 10 * gray_step;
+
+    if distance(rgb, (gray, gray, gray)) < distance(rgb, cube_rgb) {
+        232 + gray_step
+    } else {
+        cube
+    }
+}
+
+/// Closest of the 16 basic ANSI colors, using the xterm default palette.
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    const PALETTE: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::Gray, (229, 229, 229)),
+        (Color::DarkGray, (127, 127, 127)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (92, 92, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    PALETTE
+        .iter()
+        .min_by_key(|(_, palette_rgb)| distance(rgb, *palette_rgb))
+        .map(|&(color, _)| color)
+        .unwrap_or(Color::White)
+}
+
+fn load_file(path: &Path, support: ColorSupport) -> Result<Theme, Box<dyn std::error::Error>> {
+    let file: ThemeFile = toml::from_str(&fs::read_to_string(path)?)?;
+    let color = |hex: &str| parse_hex(hex).map(|rgb| support.color(rgb));
+    let name = file.name.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    Ok(Theme {
+        name,
+        text: color(&file.text)?,
+        done: color(&file.done)?,
+        skipped: color(&file.skipped)?,
+        error: color(&file.error)?,
+        accent: color(&file.accent)?,
+        background: file.background.as_deref().map(color).transpose()?,
+    })
+}
+
+pub fn themes_dir() -> PathBuf {
+    config_dir().join("themes")
+}
+
+/// Where themes converted from monkeytype are kept.
+fn imported_dir() -> PathBuf {
+    cache_dir().join("themes")
+}
+
+fn theme_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+/// Built-in schemes, then the user's theme files, then imported monkeytype
+/// themes, each sorted by file name. Files that fail to parse are skipped.
+pub fn load() -> Vec<Theme> {
+    let support = ColorSupport::detect();
+    let mut themes: Vec<Theme> = ColorScheme::ALL.into_iter().map(Theme::from).collect();
+
+    // The user's files override built-in schemes of the same name
+    for path in theme_files(&themes_dir()) {
+        if let Ok(theme) = load_file(&path, support) {
+            themes.retain(|existing| existing.name != theme.name);
+            themes.push(theme);
+        }
+    }
+
+    // Imported themes never shadow a built-in or user theme
+    for path in theme_files(&imported_dir()) {
+        if let Ok(theme) = load_file(&path, support) {
+            if !themes.iter().any(|existing| existing.name == theme.name) {
+                themes.push(theme);
+            }
+        }
+    }
+
+    themes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_short_long_and_alpha_hex() {
+        assert_eq!(parse_hex("#fa0"), Ok((0xff, 0xaa, 0x00)));
+        assert_eq!(parse_hex("#fa08"), Ok((0xff, 0xaa, 0x00)));
+        assert_eq!(parse_hex("1e2b3c"), Ok((0x1e, 0x2b, 0x3c)));
+        assert_eq!(parse_hex(" #1E2B3C80 "), Ok((0x1e, 0x2b, 0x3c)));
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        for hex in ["", "#", "#12", "#12345", "#ggg", "#12345g", "aééb", "#ééé"] {
+            assert!(parse_hex(hex).is_err(), "{hex}");
+        }
+    }
+}
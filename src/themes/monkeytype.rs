@@ -0,0 +1,171 @@
+use serde::Deserialize;
+use std::fs;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::{imported_dir, ThemeFile};
+
+const BASE_URL: &str =
+    "https://raw.githubusercontent.com/monkeytypegame/monkeytype/refs/heads/master/frontend/static/themes";
+
+/// Monkeytype's default error color, used when a theme doesn't set one.
+const DEFAULT_ERROR: &str = "#ca4754";
+
+/// How many theme stylesheets are fetched at once.
+const CONCURRENT_DOWNLOADS: usize = 8;
+
+/// An entry of monkeytype's `_list.json` theme index.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ListedTheme {
+    name: String,
+    bg_color: String,
+    main_color: String,
+    sub_color: String,
+    text_color: String,
+}
+
+/// Reads a `--name: value;` custom property from a theme stylesheet.
+fn css_var(css: &str, name: &str) -> Option<String> {
+    let start = css.find(&format!("--{name}:"))? + name.len() + 3;
+    let value = css[start..].split(';').next()?.trim();
+    value.starts_with('#').then(|| value.to_string())
+}
+
+/// Whether a name from the index is safe to use as a file name in the theme
+/// cache.
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// Maps monkeytype's palette onto tiddy's: untyped text uses the sub color,
+/// typed text the text color. Skipped text shares the sub color, since tiddy
+/// also uses it for labels, the ghost caret and race bars.
+fn convert(listed: &ListedTheme, css: Option<&str>) -> ThemeFile {
+    let var = |name: &str, fallback: &str| {
+        css.and_then(|css| css_var(css, name))
+            .unwrap_or_else(|| fallback.to_string())
+    };
+    let sub = var("sub-color", &listed.sub_color);
+
+    ThemeFile {
+        name: Some(listed.name.clone()),
+        background: Some(var("bg-color", &listed.bg_color)),
+        text: sub.clone(),
+        done: var("text-color", &listed.text_color),
+        skipped: sub,
+        error: var("error-color", DEFAULT_ERROR),
+        accent: var("main-color", &listed.main_color),
+    }
+}
+
+async fn fetch_css(name: &str) -> Result<String, reqwest::Error> {
+    reqwest::get(format!("{BASE_URL}/{name}.css"))
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
+
+/// Downloads the named monkeytype themes (or all of them when `names` is empty)
+/// into the imported themes cache. Returns the names that were written.
+pub async fn import(names: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut listed: Vec<ListedTheme> = reqwest::get(format!("{BASE_URL}/_list.json"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    // Names become file names, so anything that could leave the cache is
+    // skipped
+    listed.retain(|theme| is_safe_name(&theme.name));
+
+    let wanted: Vec<ListedTheme> = if names.is_empty() {
+        listed
+    } else {
+        let missing: Vec<&str> = names
+            .iter()
+            .filter(|name| !listed.iter().any(|theme| &theme.name == *name))
+            .map(|name| name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("unknown monkeytype theme(s): {}", missing.join(", ")).into());
+        }
+        listed
+            .into_iter()
+            .filter(|theme| names.contains(&theme.name))
+            .collect()
+    };
+
+    let dir = imported_dir();
+    fs::create_dir_all(&dir)?;
+
+    let permits = Arc::new(Semaphore::new(CONCURRENT_DOWNLOADS));
+    let mut tasks = JoinSet::new();
+    for theme in wanted {
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire().await;
+            // Fall back to the index colors if the stylesheet can't be fetched
+            let css = fetch_css(&theme.name).await.ok();
+            (theme.name.clone(), convert(&theme, css.as_deref()))
+        });
+    }
+
+    let mut imported = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        let (name, file) = result?;
+        fs::write(dir.join(format!("{name}.toml")), toml::to_string(&file)?)?;
+        imported.push(name);
+    }
+    imported.sort();
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed() -> ListedTheme {
+        ListedTheme {
+            name: "serika".to_string(),
+            bg_color: "#111111".to_string(),
+            main_color: "#222222".to_string(),
+            sub_color: "#333333".to_string(),
+            text_color: "#444444".to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_hex_custom_properties() {
+        let css = ":root {\n  --bg-color: #323437;\n  --main-color:#e2b714 ;\n  --sub-color: var(--x);\n}";
+        assert_eq!(css_var(css, "bg-color"), Some("#323437".to_string()));
+        assert_eq!(css_var(css, "main-color"), Some("#e2b714".to_string()));
+        assert_eq!(css_var(css, "sub-color"), None);
+        assert_eq!(css_var(css, "text-color"), None);
+    }
+
+    #[test]
+    fn converts_stylesheet_over_index_colors() {
+        let css = "--bg-color: #000000; --sub-color: #555555; --error-color: #ff0000; --error-extra-color: #880000;";
+        let file = convert(&listed(), Some(css));
+        assert_eq!(file.background.as_deref(), Some("#000000"));
+        assert_eq!(file.text, "#555555");
+        assert_eq!(file.skipped, "#555555");
+        assert_eq!(file.error, "#ff0000");
+        assert_eq!(file.done, "#444444");
+        assert_eq!(file.accent, "#222222");
+
+        let file = convert(&listed(), None);
+        assert_eq!(file.skipped, "#333333");
+        assert_eq!(file.error, DEFAULT_ERROR);
+    }
+
+    #[test]
+    fn rejects_names_that_leave_the_cache() {
+        assert!(is_safe_name("serika_dark"));
+        for name in ["", "../../../.config/tiddy/config", "a/b", "a\\b", ".."] {
+            assert!(!is_safe_name(name), "{name} should be rejected");
+        }
+    }
+}
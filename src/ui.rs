@@ -1,5 +1,11 @@
 use ratatui::{prelude::*, widgets::*};
 
+use crate::app::Results;
+use crate::quotes::Quote;
+use crate::race::Racer;
+use crate::themes::Theme;
+use crate::words::is_right_to_left_char;
+
 pub trait ThemeColors {
     fn text(self) -> Color;
     fn done(self) -> Color;
@@ -50,15 +56,12 @@ impl ThemeColors for ColorScheme {
 }
 
 impl ColorScheme {
-    pub fn get(index: usize) -> Self {
-        const SCHEMES: [ColorScheme; 4] = [
-            ColorScheme::Gruvbox,
-            ColorScheme::Dracula,
-            ColorScheme::Nord,
-            ColorScheme::Solarized,
-        ];
-        SCHEMES[index % SCHEMES.len()]
-    }
+    pub const ALL: [ColorScheme; 4] = [
+        ColorScheme::Gruvbox,
+        ColorScheme::Dracula,
+        ColorScheme::Nord,
+        ColorScheme::Solarized,
+    ];
 }
 
 impl std::fmt::Display for ColorScheme {
@@ -91,6 +94,10 @@ impl CursorStyle {
             _ => CursorStyle::Default,
         }
     }
+
+    pub fn index_of(name: &str) -> Option<usize> {
+        (0..3).find(|&index| Self::cycle(index).to_string() == name)
+    }
 }
 
 impl std::fmt::Display for CursorStyle {
@@ -101,50 +108,32 @@ impl std::fmt::Display for CursorStyle {
 }
 
 pub struct RenderConfig<'a> {
-    pub target: &'a str,
-    pub input: &'a str,
-    pub wpm: f64,
+    pub target: &'a [String],
+    pub input: &'a [Option<String>],
+    pub net_wpm: f64,
+    pub raw_wpm: f64,
     pub accuracy: f64,
-    pub scheme_index: usize,
+    pub consistency: f64,
+    pub theme: &'a Theme,
     pub cursor_style_index: usize,
-    pub is_done: bool,
-    pub restart_countdown: Option<u64>,
+    pub right_to_left: bool,
+    /// Position of the ghost caret, if racing one
+    pub ghost: Option<usize>,
+    /// Everyone's progress in a race, shown under the text
+    pub racers: &'a [Racer],
+    /// Key hints shown under the text
+    pub help: &'a str,
 }
 
 pub fn render_typing_test<B: Backend>(frame: &mut Frame<B>, config: RenderConfig) {
-    let scheme = ColorScheme::get(config.scheme_index);
-
-    // Create centered layout
-    let area = frame.size();
-    let content_width = area.width.clamp(40, 80); // Max 80 chars, min 40 chars
-    let horizontal_margin = (area.width.saturating_sub(content_width)) / 2;
-
-    let vertical_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([
-            Constraint::Length(1), // Title
-            Constraint::Length(2), // Spacing
-            Constraint::Min(5),    // Main content
-            Constraint::Length(2), // Spacing
-            Constraint::Length(1), // Help
-        ])
-        .split(area);
-
-    let centered_chunks: Vec<Rect> = vertical_chunks
-        .iter()
-        .map(|&chunk| Rect {
-            x: chunk.x + horizontal_margin,
-            y: chunk.y,
-            width: content_width,
-            height: chunk.height,
-        })
-        .collect();
+    let scheme = config.theme;
+    let centered_chunks = centered_layout(frame.size());
+    render_background(frame, scheme);
 
     // Title
     let title = format!(
-        "tiddy ({}) | wpm: {:.0} | acc: {:.0}%",
-        scheme, config.wpm, config.accuracy
+        "tiddy ({}) | wpm: {:.0} | raw: {:.0} | acc: {:.0}% | con: {:.0}%",
+        scheme, config.net_wpm, config.raw_wpm, config.accuracy, config.consistency
     );
     frame.render_widget(
         Paragraph::new(title)
@@ -153,27 +142,162 @@ pub fn render_typing_test<B: Backend>(frame: &mut Frame<B>, config: RenderConfig
         centered_chunks[0],
     );
 
-    // Main text content (skip spacing chunks at index 1 and 3)
+    // Main text content (skip spacing chunks at index 1 and 3), with racers
+    // in rows of their own below it
+    let racer_rows = config.racers.len() as u16;
+    let content = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),
+            Constraint::Length(racer_rows.min(1)), // Spacing
+            Constraint::Length(racer_rows),
+        ])
+        .split(centered_chunks[2]);
     let spans = create_text_spans(
         config.target,
         config.input,
         scheme,
         config.cursor_style_index,
+        config.ghost,
+    );
+    let lines = wrap_lines(config.target, spans, content[0].width as usize);
+    // Long texts scroll so the line after the cursor stays in view
+    let cursor_row = lines
+        .iter()
+        .rposition(|(start, _)| *start <= config.input.len())
+        .unwrap_or(0);
+    let height = content[0].height as usize;
+    let scroll = (cursor_row + 2)
+        .saturating_sub(height)
+        .min(lines.len().saturating_sub(height));
+    let lines: Vec<Line> = lines
+        .into_iter()
+        .map(|(start, line)| {
+            if config.right_to_left {
+                let end = start + line.len();
+                Line::from(mirror_line(&config.target[start..end], line))
+            } else {
+                Line::from(line)
+            }
+        })
+        .collect();
+    let alignment = if config.right_to_left {
+        Alignment::Right
+    } else {
+        Alignment::Left
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .alignment(alignment)
+            .scroll((scroll as u16, 0)),
+        content[0],
     );
+    if !config.racers.is_empty() {
+        frame.render_widget(
+            Paragraph::new(racer_lines(
+                config.racers,
+                scheme,
+                content[2].width as usize,
+            )),
+            content[2],
+        );
+    }
+
+    // Help
     frame.render_widget(
-        Paragraph::new(Line::from(spans))
+        Paragraph::new(config.help)
+            .fg(scheme.text())
+            .alignment(Alignment::Center),
+        centered_chunks[4],
+    );
+}
+
+pub struct ResultsConfig<'a> {
+    pub results: &'a Results,
+    pub theme: &'a Theme,
+    pub restart_countdown: Option<u64>,
+    pub quote: Option<&'a Quote>,
+}
+
+pub fn render_results<B: Backend>(frame: &mut Frame<B>, config: ResultsConfig) {
+    let scheme = config.theme;
+    let results = config.results;
+    let centered_chunks = centered_layout(frame.size());
+    render_background(frame, scheme);
+
+    frame.render_widget(
+        Paragraph::new(format!("tiddy ({scheme}) | results"))
+            .fg(scheme.accent())
+            .alignment(Alignment::Center),
+        centered_chunks[0],
+    );
+
+    let label = Style::default().fg(scheme.skipped());
+    let value = Style::default().fg(scheme.text());
+    let row = |name: &'static str, text: String| {
+        Line::from(vec![
+            Span::styled(format!("{name:<16}"), label),
+            Span::styled(text, value),
+        ])
+    };
+
+    let chars = results.chars;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("wpm ", label),
+            Span::styled(
+                format!("{:.0}", results.net_wpm),
+                Style::default()
+                    .fg(scheme.accent())
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("   acc ", label),
+            Span::styled(
+                format!("{:.0}%", results.accuracy),
+                Style::default()
+                    .fg(scheme.accent())
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(""),
+        row("raw", format!("{:.0}", results.raw_wpm)),
+        row("final accuracy", format!("{:.0}%", results.final_accuracy)),
+        row("consistency", format!("{:.0}%", results.consistency)),
+        row(
+            "characters",
+            format!(
+                "{}/{}/{}/{}",
+                chars.correct, chars.incorrect, chars.extra, chars.missed
+            ),
+        ),
+        row("time", format!("{:.1}s", results.duration)),
+        row("mode", results.mode.to_string()),
+        row("word list", results.word_list.clone()),
+    ];
+    if let Some(quote) = config.quote {
+        lines.push(row("source", format!("{} (#{})", quote.source, quote.id)));
+    }
+    let content_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(lines.len() as u16), // Summary
+            Constraint::Length(1),                  // Spacing
+            Constraint::Min(6),                     // Chart
+        ])
+        .split(centered_chunks[2]);
+
+    frame.render_widget(
+        Paragraph::new(lines)
             .wrap(Wrap { trim: true })
             .alignment(Alignment::Left),
-        centered_chunks[2],
+        content_chunks[0],
     );
+    render_wpm_chart(frame, results, scheme, content_chunks[2]);
 
-    // Help
     let help = if let Some(countdown) = config.restart_countdown {
-        format!("Auto-restart in {countdown}s (any key to cancel) | Ctrl+R restart | Esc quit")
-    } else if config.is_done {
-        "Test complete | Ctrl+R restart | Esc quit".to_string()
+        format!("Auto-restart in {countdown}s | Enter/Ctrl+R restart | Esc quit")
     } else {
-        "Ctrl+R restart | Esc quit".to_string()
+        "characters: correct/incorrect/extra/missed | Enter/Ctrl+R restart | Esc quit".to_string()
     };
     frame.render_widget(
         Paragraph::new(help)
@@ -183,47 +307,244 @@ pub fn render_typing_test<B: Backend>(frame: &mut Frame<B>, config: RenderConfig
     );
 }
 
-fn create_text_spans<'a>(
-    target: &'a str,
-    input: &'a str,
-    scheme: ColorScheme,
+/// Plots net and raw wpm per second, with seconds that had errors as dots.
+/// Errors have their own scale, from none at the bottom to the most in any
+/// second at the top.
+fn render_wpm_chart<B: Backend>(
+    frame: &mut Frame<B>,
+    results: &Results,
+    scheme: &Theme,
+    area: Rect,
+) {
+    let wpm: Vec<(f64, f64)> = results
+        .snapshots
+        .iter()
+        .map(|s| (s.second, s.wpm))
+        .collect();
+    let raw: Vec<(f64, f64)> = results
+        .snapshots
+        .iter()
+        .map(|s| (s.second, s.raw_wpm))
+        .collect();
+    let Some(last) = results.snapshots.last() else {
+        return;
+    };
+
+    let max_second = last.second;
+    let max_wpm = wpm
+        .iter()
+        .chain(&raw)
+        .map(|&(_, y)| y)
+        .fold(0.0, f64::max)
+        .max(10.0)
+        .ceil();
+    let max_errors = results
+        .snapshots
+        .iter()
+        .map(|s| s.errors)
+        .max()
+        .unwrap_or(0);
+    let errors: Vec<(f64, f64)> = results
+        .snapshots
+        .iter()
+        .filter(|s| s.errors > 0)
+        .map(|s| (s.second, s.errors as f64 / max_errors as f64 * max_wpm))
+        .collect();
+    let errors_name = format!("errors (top {max_errors})");
+
+    let datasets = vec![
+        Dataset::default()
+            .name("raw")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(scheme.skipped()))
+            .data(&raw),
+        Dataset::default()
+            .name("wpm")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(scheme.accent()))
+            .data(&wpm),
+        Dataset::default()
+            .name(errors_name)
+            .marker(symbols::Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(scheme.error()))
+            .data(&errors),
+    ];
+
+    let label = Style::default().fg(scheme.skipped());
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(label)
+                .bounds([0.0, max_second])
+                .labels(vec![
+                    Span::styled("0", label),
+                    Span::styled(format!("{max_second:.1}s"), label),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(label)
+                .bounds([0.0, max_wpm])
+                .labels(vec![
+                    Span::styled("0", label),
+                    Span::styled(format!("{max_wpm:.0}"), label),
+                ]),
+        );
+    frame.render_widget(chart, area);
+}
+
+fn render_background<B: Backend>(frame: &mut Frame<B>, scheme: &Theme) {
+    if let Some(background) = scheme.background() {
+        frame.render_widget(
+            Block::default().style(Style::default().bg(background)),
+            frame.size(),
+        );
+    }
+}
+
+/// One line per racer: place, name, a progress bar and speed.
+fn racer_lines(racers: &[Racer], scheme: &Theme, width: usize) -> Vec<Line<'static>> {
+    let bar_width = width.saturating_sub(34).max(10);
+    racers
+        .iter()
+        .map(|racer| {
+            let filled = (racer.progress * bar_width as f64).round() as usize;
+            let place = racer
+                .place
+                .map_or("  ".to_string(), |place| format!("{place:>2}"));
+            let color = if racer.place.is_some() {
+                scheme.done()
+            } else {
+                scheme.accent()
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{place} {:<14.14} ", racer.name),
+                    Style::default().fg(scheme.text()),
+                ),
+                Span::styled("█".repeat(filled), Style::default().fg(color)),
+                Span::styled(
+                    "·".repeat(bar_width.saturating_sub(filled)),
+                    Style::default().fg(scheme.skipped()),
+                ),
+                Span::styled(
+                    format!(" {:>5.0} wpm", racer.wpm),
+                    Style::default().fg(scheme.text()),
+                ),
+            ])
+        })
+        .collect()
+}
+
+/// A full screen of racers, for the lobby and the final standings.
+pub fn render_standings<B: Backend>(
+    frame: &mut Frame<B>,
+    racers: &[Racer],
+    scheme: &Theme,
+    title: &str,
+    help: &str,
+) {
+    let centered_chunks = centered_layout(frame.size());
+    render_background(frame, scheme);
+
+    frame.render_widget(
+        Paragraph::new(title.to_string())
+            .fg(scheme.accent())
+            .alignment(Alignment::Center),
+        centered_chunks[0],
+    );
+
+    // Finishers first in order, then whoever is furthest along
+    let mut racers = racers.to_vec();
+    racers.sort_by(|a, b| match (a.place, b.place) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.progress.total_cmp(&a.progress),
+    });
+    frame.render_widget(
+        Paragraph::new(racer_lines(
+            &racers,
+            scheme,
+            centered_chunks[2].width as usize,
+        )),
+        centered_chunks[2],
+    );
+
+    frame.render_widget(
+        Paragraph::new(help.to_string())
+            .fg(scheme.text())
+            .alignment(Alignment::Center),
+        centered_chunks[4],
+    );
+}
+
+/// Splits the screen into title, content and help rows, centered horizontally.
+fn centered_layout(area: Rect) -> Vec<Rect> {
+    let content_width = area.width.clamp(40, 80); // Max 80 chars, min 40 chars
+    let horizontal_margin = (area.width.saturating_sub(content_width)) / 2;
+
+    let vertical_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(1), // Title
+            Constraint::Length(2), // Spacing
+            Constraint::Min(5),    // Main content
+            Constraint::Length(2), // Spacing
+            Constraint::Length(1), // Help
+        ])
+        .split(area);
+
+    vertical_chunks
+        .iter()
+        .map(|&chunk| Rect {
+            x: chunk.x + horizontal_margin,
+            y: chunk.y,
+            width: content_width,
+            height: chunk.height,
+        })
+        .collect()
+}
+
+fn create_text_spans(
+    target: &[String],
+    input: &[Option<String>],
+    scheme: &Theme,
     cursor_style_index: usize,
-) -> Vec<Span<'a>> {
+    ghost: Option<usize>,
+) -> Vec<Span<'static>> {
     let mut spans = Vec::new();
 
-    // Render typed characters
-    for (i, ch) in input.chars().enumerate() {
-        if let Some(target_ch) = target.chars().nth(i) {
-            if ch == '#' {
+    // Render typed graphemes
+    for (typed, expected) in input.iter().zip(target) {
+        match typed {
+            None => {
                 // Show skipped characters with dedicated skipped color
                 spans.push(Span::styled(
-                    target_ch.to_string(),
+                    visible(expected),
                     Style::default().fg(scheme.skipped()),
                 ));
-            } else if ch == target_ch {
+            }
+            Some(g) if g == expected => {
                 // Correctly typed character
-                spans.push(Span::styled(
-                    ch.to_string(),
-                    Style::default().fg(scheme.done()),
-                ));
-            } else {
+                spans.push(Span::styled(visible(g), Style::default().fg(scheme.done())));
+            }
+            Some(g) => {
                 // Incorrectly typed character
                 spans.push(Span::styled(
-                    ch.to_string(),
+                    visible(g),
                     Style::default().fg(scheme.error()),
                 ));
             }
-        } else {
-            // Input is longer than target
-            spans.push(Span::styled(
-                ch.to_string(),
-                Style::default().fg(scheme.error()),
-            ));
         }
     }
 
-    // Render remaining target characters
-    for (i, ch) in target.chars().enumerate().skip(input.len()) {
+    // Render remaining target graphemes
+    for (i, g) in target.iter().enumerate().skip(input.len()) {
         let style = if i == input.len() {
             // Cursor position
             let base_style = Style::default().fg(scheme.accent());
@@ -231,8 +552,211 @@ fn create_text_spans<'a>(
         } else {
             Style::default().fg(scheme.text())
         };
-        spans.push(Span::styled(ch.to_string(), style));
+        let text = if i == input.len() && g == "\n" {
+            "↵".to_string()
+        } else {
+            visible(g)
+        };
+        spans.push(Span::styled(text, style));
+    }
+
+    // Ghost caret, drawn in the cursor shape the real cursor isn't using
+    if let Some(span) = ghost
+        .filter(|&ghost| ghost != input.len())
+        .and_then(|ghost| spans.get_mut(ghost))
+    {
+        let shape = match CursorStyle::cycle(cursor_style_index) {
+            CursorStyle::Underline => CursorStyle::Block,
+            _ => CursorStyle::Underline,
+        };
+        span.style = shape.apply(span.style.fg(scheme.skipped()));
+    }
+
+    spans
+}
+
+/// Line breaks are drawn by splitting lines, not as characters.
+fn visible(g: &str) -> String {
+    if g == "\n" {
+        String::new()
+    } else {
+        g.to_string()
+    }
+}
+
+/// Whether a grapheme reads left-to-right (digits and Latin or other letters),
+/// right-to-left, or takes the direction of its neighbours (spaces and
+/// punctuation).
+fn direction(g: &str) -> Option<bool> {
+    let ch = g.chars().next()?;
+    if ch.is_ascii_digit() {
+        Some(true)
+    } else if ch.is_alphabetic() {
+        Some(!is_right_to_left_char(ch))
+    } else {
+        None
     }
+}
+
+/// Mirrors a line of right-to-left text so it reads (and the cursor moves)
+/// leftward. Runs of digits and Latin words keep their left-to-right order
+/// inside it, along with the spaces and punctuation between them.
+fn mirror_line<'a>(target: &[String], mut spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+    let strong: Vec<Option<bool>> = target.iter().map(|g| direction(g)).collect();
+    let mut left_to_right: Vec<bool> = (0..strong.len())
+        .map(|i| {
+            strong[i].unwrap_or_else(|| {
+                let before = strong[..i].iter().rev().find_map(|&d| d);
+                let after = strong[i + 1..].iter().find_map(|&d| d);
+                before == Some(true) && after == Some(true)
+            })
+        })
+        .collect();
 
+    spans.reverse();
+    left_to_right.reverse();
+    let mut i = 0;
+    while i < spans.len() {
+        let run = left_to_right[i..].iter().take_while(|&&ltr| ltr).count();
+        spans[i..i + run].reverse();
+        i += run.max(1);
+    }
     spans
 }
+
+/// Wraps the per-grapheme spans at word boundaries to fit `width`, breaking
+/// lines wherever the target has a newline. Indentation is kept, and words
+/// longer than a line are split. Each line comes with the index of its first
+/// grapheme.
+fn wrap_lines<'a>(
+    target: &[String],
+    spans: Vec<Span<'a>>,
+    width: usize,
+) -> Vec<(usize, Vec<Span<'a>>)> {
+    let mut lines: Vec<(usize, Vec<Span<'a>>)> = vec![(0, Vec::new())];
+    let mut line_width = 0;
+    let mut word: Vec<Span<'a>> = Vec::new();
+    let mut word_start = 0;
+
+    let count = spans.len();
+    for (i, (span, expected)) in spans.into_iter().zip(target).enumerate() {
+        word.push(span);
+        let newline = expected == "\n";
+        if expected != " " && !newline && i + 1 < count {
+            continue;
+        }
+
+        let word_width: usize = word.iter().map(Span::width).sum();
+        if line_width > 0 && line_width + word_width > width {
+            lines.push((word_start, Vec::new()));
+            line_width = 0;
+        }
+        for (offset, span) in word.drain(..).enumerate() {
+            if line_width > 0 && line_width + span.width() > width {
+                lines.push((word_start + offset, Vec::new()));
+                line_width = 0;
+            }
+            line_width += span.width();
+            if let Some((_, line)) = lines.last_mut() {
+                line.push(span);
+            }
+        }
+        word_start = i + 1;
+        if newline {
+            lines.push((word_start, Vec::new()));
+            line_width = 0;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    fn wrap(text: &str, width: usize) -> Vec<(usize, String)> {
+        let target: Vec<String> = text.graphemes(true).map(str::to_string).collect();
+        let spans = target.iter().map(|g| Span::raw(visible(g))).collect();
+        wrap_lines(&target, spans, width)
+            .into_iter()
+            .map(|(start, line)| {
+                (
+                    start,
+                    line.iter().map(|span| span.content.as_ref()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Wraps and mirrors right-to-left text, marking the cursor with `|`.
+    fn mirror(text: &str, width: usize, cursor: usize) -> Vec<String> {
+        let target: Vec<String> = text.graphemes(true).map(str::to_string).collect();
+        let spans = target
+            .iter()
+            .enumerate()
+            .map(|(i, g)| {
+                Span::raw(if i == cursor {
+                    format!("|{g}")
+                } else {
+                    g.clone()
+                })
+            })
+            .collect();
+        wrap_lines(&target, spans, width)
+            .into_iter()
+            .map(|(start, line)| {
+                let end = start + line.len();
+                mirror_line(&target[start..end], line)
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mirrors_right_to_left_lines_after_wrapping() {
+        assert_eq!(mirror("אב גד הו", 6, 99), [" דג בא", "וה"]);
+    }
+
+    #[test]
+    fn keeps_numbers_and_latin_words_left_to_right() {
+        assert_eq!(mirror("אב 123 גד", 20, 99), ["דג 123 בא"]);
+        assert_eq!(mirror("אב hello world", 20, 99), ["hello world בא"]);
+        assert_eq!(mirror("אב 3.14.", 20, 99), [".3.14 בא"]);
+    }
+
+    #[test]
+    fn cursor_moves_leftward_except_inside_numbers() {
+        assert_eq!(mirror("אב 12", 20, 0), ["12 ב|א"]);
+        assert_eq!(mirror("אב 12", 20, 1), ["12 |בא"]);
+        assert_eq!(mirror("אב 12", 20, 3), ["|12 בא"]);
+        assert_eq!(mirror("אב 12", 20, 4), ["1|2 בא"]);
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_keeps_indentation() {
+        assert_eq!(
+            wrap("one two three", 8),
+            [(0, "one two ".to_string()), (8, "three".to_string())]
+        );
+        assert_eq!(
+            wrap("fn x\n    y", 20),
+            [(0, "fn x".to_string()), (5, "    y".to_string())]
+        );
+    }
+
+    #[test]
+    fn splits_words_longer_than_a_line() {
+        assert_eq!(
+            wrap("abcdefg hi", 4),
+            [
+                (0, "abcd".to_string()),
+                (4, "efg ".to_string()),
+                (8, "hi".to_string())
+            ]
+        );
+    }
+}
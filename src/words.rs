@@ -1,11 +1,15 @@
 use rand::seq::SliceRandom;
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
 
 #[derive(Deserialize, Debug, Clone)]
-struct WordList {
-    words: Vec<String>,
+pub struct WordList {
+    pub words: Vec<String>,
+    #[serde(default, rename = "rightToLeft")]
+    right_to_left: bool,
 }
 
 impl Default for WordList {
@@ -17,10 +21,31 @@ impl Default for WordList {
             .iter()
             .map(|&s| s.to_string())
             .collect(),
+            right_to_left: false,
         }
     }
 }
 
+/// Word lists compiled in from `words/`, as `(file name, contents)`. Empty
+/// without the `bundled-words` feature.
+const BUNDLED: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/bundled_words.rs"));
+
+fn bundled(filename: &str) -> Option<&'static str> {
+    BUNDLED
+        .iter()
+        .find(|(name, _)| *name == filename)
+        .map(|(_, content)| *content)
+}
+
+/// Branch of monkeytype's repository word lists come from unless pinned.
+pub const DEFAULT_WORD_LIST_REF: &str = "master";
+
+/// Monkeytype's languages directory, listed through the GitHub API.
+pub const LANGUAGE_INDEX_URL: &str =
+    "https://api.github.com/repos/monkeytypegame/monkeytype/contents/frontend/static/languages";
+
+/// Word lists known to exist upstream, used until (and alongside) a fetched
+/// language index.
 const AVAILABLE_LANGUAGES: &[&str] = &[
     "afrikaans",
     "afrikaans_10k",
@@ -436,26 +461,83 @@ const AVAILABLE_LANGUAGES: &[&str] = &[
     "zulu",
 ];
 
+/// Every word list that can be picked: the built-in names merged with the
+/// last fetched language index.
 pub fn languages() -> Vec<String> {
-    AVAILABLE_LANGUAGES
+    let mut languages: Vec<String> = AVAILABLE_LANGUAGES
         .iter()
-        .map(|&lang| format!("{lang}.json"))
-        .collect()
+        .map(|lang| lang.to_string())
+        .chain(cached_index())
+        .map(|lang| format!("{lang}.json"))
+        .collect();
+    languages.sort();
+    languages.dedup();
+    languages
+}
+
+/// An entry of a language index: either a plain name, or a file with a
+/// `name` like in GitHub's directory listings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IndexEntry {
+    Name(String),
+    File { name: String },
+}
+
+fn index_path() -> PathBuf {
+    cache_dir().join("languages").join("index.json")
 }
 
-fn cache_dir() -> PathBuf {
+fn cached_index() -> Vec<String> {
+    fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Fetches the language index at `url` and caches the names in it.
+/// Returns how many word lists it lists.
+pub async fn refresh_languages(url: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    // GitHub's API turns away requests without a user agent
+    let response = reqwest::Client::builder()
+        .user_agent("tiddy")
+        .build()?
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+    let entries: Vec<IndexEntry> = response
+        .json()
+        .await
+        .map_err(|e| format!("{url} isn't a language index: {e}"))?;
+
+    let mut names: Vec<String> = entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            IndexEntry::Name(name) => Some(name.trim_end_matches(".json").to_string()),
+            IndexEntry::File { name } => name.strip_suffix(".json").map(|name| name.to_string()),
+        })
+        // Skips monkeytype's own `_groups.json` and the like
+        .filter(|name| !name.is_empty() && !name.starts_with('_'))
+        .collect();
+    names.sort();
+    names.dedup();
+
+    fs::create_dir_all(index_path().parent().unwrap())?;
+    fs::write(index_path(), serde_json::to_string(&names)?)?;
+    Ok(names.len())
+}
+
+pub fn cache_dir() -> PathBuf {
     dirs::cache_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("tiddy")
 }
 
+/// Word lists that work offline: downloaded ones and the bundled ones.
 pub fn downloaded() -> Vec<String> {
-    let dir = cache_dir();
-    if !dir.exists() {
-        return vec![];
-    }
-
-    let mut files = fs::read_dir(&dir)
+    let mut files = fs::read_dir(cache_dir())
         .ok()
         .map(|entries| {
             entries
@@ -465,17 +547,67 @@ pub fn downloaded() -> Vec<String> {
                 .collect::<Vec<_>>()
         })
         .unwrap_or_default();
+    files.extend(BUNDLED.iter().map(|(name, _)| name.to_string()));
     files.sort();
+    files.dedup();
     files
 }
 
+/// Base URL of monkeytype's word lists at a branch, tag or commit.
+pub fn monkeytype_url(git_ref: &str) -> String {
+    format!(
+        "https://raw.githubusercontent.com/monkeytypegame/monkeytype/{git_ref}/frontend/static/languages"
+    )
+}
+
+/// How much of a word list has been downloaded, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    pub bytes: u64,
+    /// Size of the list, if the server said
+    pub total: Option<u64>,
+}
+
+/// Reads `filename` from a base URL, which may be a `file://` directory,
+/// reporting progress as the body comes in.
+async fn fetch(
+    base: &str,
+    filename: &str,
+    progress: &(impl Fn(Progress) + Sync),
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let url = format!("{}/{filename}", base.trim_end_matches('/'));
+    if let Some(path) = url.strip_prefix("file://") {
+        let content = fs::read_to_string(path)?;
+        let bytes = content.len() as u64;
+        progress(Progress {
+            bytes,
+            total: Some(bytes),
+        });
+        return Ok(content);
+    }
+
+    let mut response = reqwest::get(&url).await?.error_for_status()?;
+    let total = response.content_length();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        progress(Progress {
+            bytes: body.len() as u64,
+            total,
+        });
+    }
+    Ok(String::from_utf8(body)?)
+}
+
+/// Returns the words of a list, downloading it into the cache `dir` from the
+/// first of `sources` that has it if it isn't cached or bundled.
 pub async fn download(
+    dir: &Path,
     filename: &str,
+    sources: &[String],
+    progress: impl Fn(Progress) + Sync,
 ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
-    let url = format!("https://raw.githubusercontent.com/monkeytypegame/monkeytype/refs/heads/master/frontend/static/languages/{filename}");
-
-    let dir = cache_dir();
-    fs::create_dir_all(&dir)?;
+    fs::create_dir_all(dir)?;
     let path = dir.join(filename);
 
     // Return cached file if exists
@@ -484,15 +616,110 @@ pub async fn download(
         let word_list: WordList = serde_json::from_str(&content)?;
         return Ok(word_list.words);
     }
+    if let Some(content) = bundled(filename) {
+        let word_list: WordList = serde_json::from_str(content)?;
+        return Ok(word_list.words);
+    }
 
     // Download, cache, and return
-    let content = reqwest::get(&url).await?.text().await?;
-    let word_list: WordList = serde_json::from_str(&content)?;
+    let content = fetch_list(filename, sources, progress).await?;
     fs::write(&path, &content)?;
+    let word_list: WordList = serde_json::from_str(&content)?;
     Ok(word_list.words)
 }
 
+/// Downloads a list from the first of `sources` that has a valid copy,
+/// without looking at or writing to the cache.
+pub async fn fetch_list(
+    filename: &str,
+    sources: &[String],
+    progress: impl Fn(Progress) + Sync,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut errors = Vec::new();
+    for source in sources {
+        // Each source counts from zero, whatever a failed one got through
+        progress(Progress::default());
+        let parsed = fetch(source, filename, &progress)
+            .await
+            .and_then(|content| {
+                serde_json::from_str::<WordList>(&content)?;
+                Ok(content)
+            });
+        match parsed {
+            Ok(content) => return Ok(content),
+            Err(e) => errors.push(format!("{source}: {e}")),
+        }
+    }
+
+    Err(format!("failed to download {filename} ({})", errors.join("; ")).into())
+}
+
+/// A word list download running in the background. Dropping it cancels
+/// the download.
+#[derive(Debug)]
+pub struct Download {
+    filename: String,
+    progress: watch::Receiver<Progress>,
+    result: oneshot::Receiver<Result<(), String>>,
+    task: JoinHandle<()>,
+}
+
+impl Download {
+    /// Starts downloading `filename` into the cache `dir` in the background.
+    pub fn start(dir: PathBuf, filename: &str, sources: Vec<String>) -> Self {
+        let (progress_sender, progress) = watch::channel(Progress::default());
+        let (result_sender, result) = oneshot::channel();
+        let name = filename.to_string();
+        let task = tokio::spawn(async move {
+            let report = |progress| {
+                progress_sender.send_replace(progress);
+            };
+            let outcome = download(&dir, &name, &sources, report)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string());
+            let _ = result_sender.send(outcome);
+        });
+
+        Self {
+            filename: filename.to_string(),
+            progress,
+            result,
+            task,
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn progress(&self) -> Progress {
+        *self.progress.borrow()
+    }
+
+    /// How the download went, once it's over.
+    pub fn finished(&mut self) -> Option<Result<(), String>> {
+        match self.result.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                Some(Err("the download stopped unexpectedly".to_string()))
+            }
+        }
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
 fn load_words(filename: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    load_word_list(filename).map(|word_list| word_list.words)
+}
+
+fn load_word_list(filename: &str) -> Result<WordList, Box<dyn std::error::Error>> {
     let paths = [
         format!("words/{filename}"),
         cache_dir().join(filename).to_string_lossy().to_string(),
@@ -501,18 +728,107 @@ fn load_words(filename: &str) -> Result<Vec<String>, Box<dyn std::error::Error>>
     for path in &paths {
         if let Ok(content) = fs::read_to_string(path) {
             let word_list: WordList = serde_json::from_str(&content)?;
-            return Ok(word_list.words);
+            return Ok(word_list);
         }
     }
+    if let Some(content) = bundled(filename) {
+        return Ok(serde_json::from_str(content)?);
+    }
 
     Err("File not found".into())
 }
 
+/// Words of a list, falling back to english and then to a few words so a
+/// test can always start.
+fn words_or_fallback(filename: &str) -> Vec<String> {
+    load_words(filename)
+        .or_else(|_| load_words("english.json"))
+        .unwrap_or_else(|_| WordList::default().words)
+}
+
 pub fn generate_words(count: usize, word_list: Option<&str>) -> Vec<String> {
     let filename = word_list.unwrap_or("english.json");
-    let words = load_words(filename).unwrap_or_else(|_| WordList::default().words);
+    let words = words_or_fallback(filename);
     words
         .choose_multiple(&mut rand::thread_rng(), count)
         .cloned()
         .collect()
 }
+
+/// Like `generate_words`, but draws each word with probability proportional
+/// to `weight` (which must be positive).
+pub fn generate_weighted_words(
+    count: usize,
+    word_list: Option<&str>,
+    weight: impl Fn(&str) -> f64,
+) -> Vec<String> {
+    let filename = word_list.unwrap_or("english.json");
+    let words = words_or_fallback(filename);
+    let mut rng = rand::thread_rng();
+    match words.choose_multiple_weighted(&mut rng, count, |word| weight(word)) {
+        Ok(chosen) => chosen.cloned().collect(),
+        Err(_) => words.choose_multiple(&mut rng, count).cloned().collect(),
+    }
+}
+
+/// Whether a word list is written right-to-left, per its `rightToLeft` flag.
+pub fn is_right_to_left(word_list: &str) -> bool {
+    load_word_list(word_list)
+        .map(|word_list| word_list.right_to_left)
+        .unwrap_or(false)
+}
+
+/// Guesses the direction of text without metadata from its first letter.
+pub fn starts_right_to_left(text: &str) -> bool {
+    text.chars()
+        .find(|ch| ch.is_alphabetic())
+        .is_some_and(is_right_to_left_char)
+}
+
+/// Whether a letter belongs to a right-to-left script (Hebrew, Arabic and
+/// neighbouring scripts).
+pub fn is_right_to_left_char(ch: char) -> bool {
+    matches!(ch, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn guesses_direction_from_the_first_letter() {
+        assert!(starts_right_to_left("שלום world"));
+        assert!(starts_right_to_left("123, مرحبا"));
+        assert!(!starts_right_to_left("hello שלום"));
+        assert!(!starts_right_to_left("42"));
+        assert!(!starts_right_to_left(""));
+    }
+
+    #[tokio::test]
+    async fn download_falls_back_to_a_local_mirror() {
+        let root = std::env::temp_dir().join(format!("tiddy-download-{}", std::process::id()));
+        let mirror = root.join("mirror");
+        let list = r#"{"name": "test_list", "words": ["one", "two"]}"#;
+        fs::create_dir_all(&mirror).unwrap();
+        fs::write(mirror.join("test_list.json"), list).unwrap();
+
+        let sources = vec![
+            format!("file://{}", root.join("missing").display()),
+            format!("file://{}", mirror.display()),
+        ];
+        let cache = root.join("cache");
+        let mut download = Download::start(cache.clone(), "test_list.json", sources);
+        let outcome = loop {
+            if let Some(outcome) = download.finished() {
+                break outcome;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+
+        assert_eq!(outcome, Ok(()));
+        assert_eq!(download.progress().total, Some(list.len() as u64));
+        assert!(cache.join("test_list.json").is_file());
+        let _ = fs::remove_dir_all(root);
+    }
+}